@@ -1,13 +1,25 @@
 #!/usr/bin/env bash
 
+set -e
+
 SELF_PATH=${BASH_SOURCE[0]:-"$(command -v -- "$0")"}
 PROJECT_ROOT="$(cd "$(dirname "$SELF_PATH")/.." && pwd)"
 
+# Set PANIC_ISOLATION=1 to build with the panic-isolation feature, poisoning only the VM that panicked instead of aborting.
+# Catching panics on wasm32 requires rebuilding std with unwinding, hence a nightly toolchain with the rust-src component.
+WASM_PACK_ARGS=()
+if [ "${PANIC_ISOLATION:-0}" = "1" ]; then
+  export RUSTUP_TOOLCHAIN=nightly
+  export RUSTFLAGS="-C panic=unwind"
+  # wasm-opt doesn't parse the exnref instructions emitted for unwinding yet.
+  WASM_PACK_ARGS=(--profile release-isolated --no-opt -- --features panic-isolation -Z build-std=std,panic_unwind)
+fi
+
 # For core sdk
 pushd $PROJECT_ROOT/sdk-shared-core-wasm-bindings
-wasm-pack build --target web
+wasm-pack build --target web "${WASM_PACK_ARGS[@]}"
 node $PROJECT_ROOT/.tools/wasm_inline.js
 
 # Cloudflare sdk
-wasm-pack build --target bundler -d ../packages/libs/restate-sdk-cloudflare-workers/patches/vm
+wasm-pack build --target bundler -d ../packages/libs/restate-sdk-cloudflare-workers/patches/vm "${WASM_PACK_ARGS[@]}"
 popd
@@ -5,5 +5,5 @@ import { __wbg_set_wasm } from "./sdk_shared_core_wasm_bindings_bg.js";
 __wbg_set_wasm(wasm);
 wasm.__wbindgen_start();
 export {
-    LogLevel, WasmCommandType, WasmHeader, WasmIdentityVerifier, WasmInput, WasmJournalMismatchBehavior, WasmResponseHead, WasmVM, cancel_handle, set_log_level, start
+    LogLevel, WasmCommandType, WasmHeader, WasmIdentityVerifier, WasmInput, WasmJournalMismatchBehavior, WasmResponseHead, WasmVM, cancel_handle, decode_protocol_messages, diff_journals, encode_protocol_messages, is_retryable, is_suspended, replay_invocation_trace, set_log_level, start
 } from "./sdk_shared_core_wasm_bindings_bg.js";
@@ -1,4 +1,4 @@
-import { fatal, vm_log } from '../core_logging.js';
+import { vm_log, vm_log_batch, vm_span_event } from '../core_logging.js';
 
 
 /**
@@ -342,13 +342,34 @@ export class WasmVM {
         const ret = wasm.wasmvm_get_response_head(this.__wbg_ptr);
         return WasmResponseHead.__wrap(ret);
     }
+    /**
+     * Returns the trace recorded so far as a JSON string, or `None` if [`WasmVMOptions::record_trace`] is disabled.
+     *
+     * The trace can be replayed with `replay_invocation_trace`.
+     * @returns {string | undefined}
+     */
+    invocation_trace() {
+        const ret = wasm.wasmvm_invocation_trace(this.__wbg_ptr);
+        if (ret[3]) {
+            throw takeFromExternrefTable0(ret[2]);
+        }
+        let v1;
+        if (ret[0] !== 0) {
+            v1 = getStringFromWasm0(ret[0], ret[1]).slice();
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v1;
+    }
     /**
      * @param {number} handle
      * @returns {boolean}
      */
     is_completed(handle) {
         const ret = wasm.wasmvm_is_completed(this.__wbg_ptr, handle);
-        return ret !== 0;
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
     }
     /**
      * @returns {boolean}
@@ -372,7 +393,10 @@ export class WasmVM {
      */
     last_command_index() {
         const ret = wasm.wasmvm_last_command_index(this.__wbg_ptr);
-        return ret;
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0];
     }
     /**
      * @param {WasmHeader[]} headers
@@ -381,11 +405,12 @@ export class WasmVM {
      * @param {boolean} disable_payload_checks
      * @param {boolean} explicit_cancellation
      * @param {WasmJournalMismatchBehavior} on_journal_mismatch
+     * @param {WasmVMOptions | null} [options]
      */
-    constructor(headers, log_level, logger_id, disable_payload_checks, explicit_cancellation, on_journal_mismatch) {
+    constructor(headers, log_level, logger_id, disable_payload_checks, explicit_cancellation, on_journal_mismatch, options) {
         const ptr0 = passArrayJsValueToWasm0(headers, wasm.__wbindgen_malloc);
         const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_new(ptr0, len0, log_level, logger_id, disable_payload_checks, explicit_cancellation, on_journal_mismatch);
+        const ret = wasm.wasmvm_new(ptr0, len0, log_level, logger_id, disable_payload_checks, explicit_cancellation, on_journal_mismatch, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -394,55 +419,13 @@ export class WasmVM {
         return this;
     }
     /**
-     * @param {string} error_message
-     * @param {string | null} [stacktrace]
-     */
-    notify_error(error_message, stacktrace) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(stacktrace) ? 0 : passStringToWasm0(stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        wasm.wasmvm_notify_error(this.__wbg_ptr, ptr0, len0, ptr1, len1);
-    }
-    /**
-     * @param {string} error_message
-     * @param {string | null | undefined} stacktrace
-     * @param {WasmCommandType} wasm_command_type
-     */
-    notify_error_for_next_command(error_message, stacktrace, wasm_command_type) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(stacktrace) ? 0 : passStringToWasm0(stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        wasm.wasmvm_notify_error_for_next_command(this.__wbg_ptr, ptr0, len0, ptr1, len1, wasm_command_type);
-    }
-    /**
-     * @param {string} error_message
-     * @param {string | null | undefined} stacktrace
-     * @param {WasmCommandType} wasm_command_type
-     * @param {number} command_index
-     * @param {string | null} [command_name]
+     * @param {WasmNotifyErrorOptions} options
      */
-    notify_error_for_specific_command(error_message, stacktrace, wasm_command_type, command_index, command_name) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(stacktrace) ? 0 : passStringToWasm0(stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        var ptr2 = isLikeNone(command_name) ? 0 : passStringToWasm0(command_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len2 = WASM_VECTOR_LEN;
-        wasm.wasmvm_notify_error_for_specific_command(this.__wbg_ptr, ptr0, len0, ptr1, len1, wasm_command_type, command_index, ptr2, len2);
-    }
-    /**
-     * @param {string} error_message
-     * @param {string | null} [stacktrace]
-     * @param {bigint | null} [delay_override]
-     */
-    notify_error_with_delay_override(error_message, stacktrace, delay_override) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(stacktrace) ? 0 : passStringToWasm0(stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        wasm.wasmvm_notify_error_with_delay_override(this.__wbg_ptr, ptr0, len0, ptr1, len1, !isLikeNone(delay_override), isLikeNone(delay_override) ? BigInt(0) : delay_override);
+    notify_error(options) {
+        const ret = wasm.wasmvm_notify_error(this.__wbg_ptr, options);
+        if (ret[1]) {
+            throw takeFromExternrefTable0(ret[0]);
+        }
     }
     /**
      * @param {Uint8Array} buffer
@@ -450,84 +433,59 @@ export class WasmVM {
     notify_input(buffer) {
         const ptr0 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
         const len0 = WASM_VECTOR_LEN;
-        wasm.wasmvm_notify_input(this.__wbg_ptr, ptr0, len0);
+        const ret = wasm.wasmvm_notify_input(this.__wbg_ptr, ptr0, len0);
+        if (ret[1]) {
+            throw takeFromExternrefTable0(ret[0]);
+        }
     }
     notify_input_closed() {
-        wasm.wasmvm_notify_input_closed(this.__wbg_ptr);
-    }
-    /**
-     * @param {number} handle
-     * @param {WasmFailure} value
-     */
-    propose_run_completion_failure(handle, value) {
-        const ret = wasm.wasmvm_propose_run_completion_failure(this.__wbg_ptr, handle, value);
+        const ret = wasm.wasmvm_notify_input_closed(this.__wbg_ptr);
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
     }
     /**
-     * @param {number} handle
-     * @param {string} error_message
-     * @param {string | null | undefined} error_stacktrace
-     * @param {bigint} attempt_duration
-     * @param {WasmExponentialRetryConfig | null} [config]
+     * Pauses the invocation after an error, instead of retrying it, so it can be resumed by an operator.
+     * Requires service protocol V7 or newer.
+     * @param {string} reason
+     * @param {string | null} [stacktrace]
      */
-    propose_run_completion_failure_transient(handle, error_message, error_stacktrace, attempt_duration, config) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    notify_pause(reason, stacktrace) {
+        const ptr0 = passStringToWasm0(reason, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(error_stacktrace) ? 0 : passStringToWasm0(error_stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        var ptr1 = isLikeNone(stacktrace) ? 0 : passStringToWasm0(stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         var len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_propose_run_completion_failure_transient(this.__wbg_ptr, handle, ptr0, len0, ptr1, len1, attempt_duration, isLikeNone(config) ? 0 : addToExternrefTable0(config));
+        const ret = wasm.wasmvm_notify_pause(this.__wbg_ptr, ptr0, len0, ptr1, len1);
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
     }
     /**
+     * Completes the run with the given result. Retryable failures are retried following `retry_policy`,
+     * defaulting to [`WasmRetryPolicy::Infinite`]. The retry policy is ignored for other results.
      * @param {number} handle
-     * @param {string} error_message
-     * @param {string | null | undefined} error_stacktrace
-     * @param {bigint} attempt_duration
-     * @param {bigint | null} [delay_override]
-     * @param {number | null} [max_retry_attempts_override]
-     * @param {bigint | null} [max_retry_duration_override]
+     * @param {WasmRunResult} result
+     * @param {WasmRetryPolicy | null} [retry_policy]
      */
-    propose_run_completion_failure_transient_with_delay_override(handle, error_message, error_stacktrace, attempt_duration, delay_override, max_retry_attempts_override, max_retry_duration_override) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(error_stacktrace) ? 0 : passStringToWasm0(error_stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_propose_run_completion_failure_transient_with_delay_override(this.__wbg_ptr, handle, ptr0, len0, ptr1, len1, attempt_duration, !isLikeNone(delay_override), isLikeNone(delay_override) ? BigInt(0) : delay_override, isLikeNone(max_retry_attempts_override) ? Number.MAX_SAFE_INTEGER : (max_retry_attempts_override) >>> 0, !isLikeNone(max_retry_duration_override), isLikeNone(max_retry_duration_override) ? BigInt(0) : max_retry_duration_override);
+    propose_run_completion(handle, result, retry_policy) {
+        const ret = wasm.wasmvm_propose_run_completion(this.__wbg_ptr, handle, result, isLikeNone(retry_policy) ? 0 : addToExternrefTable0(retry_policy));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
     }
     /**
-     * @param {number} handle
-     * @param {string} error_message
-     * @param {string | null | undefined} error_stacktrace
-     * @param {bigint} attempt_duration
+     * Changes the log level of this VM, without affecting the overall log subscriber.
+     * @param {LogLevel} level
      */
-    propose_run_completion_failure_transient_with_pause(handle, error_message, error_stacktrace, attempt_duration) {
-        const ptr0 = passStringToWasm0(error_message, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        const len0 = WASM_VECTOR_LEN;
-        var ptr1 = isLikeNone(error_stacktrace) ? 0 : passStringToWasm0(error_stacktrace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_propose_run_completion_failure_transient_with_pause(this.__wbg_ptr, handle, ptr0, len0, ptr1, len1, attempt_duration);
-        if (ret[1]) {
-            throw takeFromExternrefTable0(ret[0]);
-        }
+    set_log_level(level) {
+        wasm.wasmvm_set_log_level(this.__wbg_ptr, level);
     }
     /**
-     * @param {number} handle
-     * @param {Uint8Array} buffer
+     * @returns {WasmVMStats}
      */
-    propose_run_completion_success(handle, buffer) {
-        const ptr0 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
-        const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_propose_run_completion_success(this.__wbg_ptr, handle, ptr0, len0);
-        if (ret[1]) {
-            throw takeFromExternrefTable0(ret[0]);
-        }
+    stats() {
+        const ret = wasm.wasmvm_stats(this.__wbg_ptr);
+        return ret;
     }
     /**
      * @param {string} invocation_id
@@ -562,9 +520,10 @@ export class WasmVM {
      * @param {string | null} [scope]
      * @param {string | null} [limit_key]
      * @param {string | null} [name]
+     * @param {WasmPayloadOptions | null} [options]
      * @returns {WasmCallHandle}
      */
-    sys_call(service, handler, buffer, key, headers, idempotency_key, scope, limit_key, name) {
+    sys_call(service, handler, buffer, key, headers, idempotency_key, scope, limit_key, name, options) {
         const ptr0 = passStringToWasm0(service, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passStringToWasm0(handler, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
@@ -583,7 +542,7 @@ export class WasmVM {
         var len7 = WASM_VECTOR_LEN;
         var ptr8 = isLikeNone(name) ? 0 : passStringToWasm0(name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         var len8 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_call(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8);
+        const ret = wasm.wasmvm_sys_call(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -620,11 +579,12 @@ export class WasmVM {
     /**
      * @param {string} id
      * @param {WasmFailure} value
+     * @param {WasmPayloadOptions | null} [options]
      */
-    sys_complete_awakeable_failure(id, value) {
+    sys_complete_awakeable_failure(id, value, options) {
         const ptr0 = passStringToWasm0(id, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_complete_awakeable_failure(this.__wbg_ptr, ptr0, len0, value);
+        const ret = wasm.wasmvm_sys_complete_awakeable_failure(this.__wbg_ptr, ptr0, len0, value, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
@@ -632,13 +592,14 @@ export class WasmVM {
     /**
      * @param {string} id
      * @param {Uint8Array} buffer
+     * @param {WasmPayloadOptions | null} [options]
      */
-    sys_complete_awakeable_success(id, buffer) {
+    sys_complete_awakeable_success(id, buffer, options) {
         const ptr0 = passStringToWasm0(id, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
         const len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_complete_awakeable_success(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        const ret = wasm.wasmvm_sys_complete_awakeable_success(this.__wbg_ptr, ptr0, len0, ptr1, len1, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
@@ -646,12 +607,13 @@ export class WasmVM {
     /**
      * @param {string} key
      * @param {WasmFailure} value
+     * @param {WasmPayloadOptions | null} [options]
      * @returns {number}
      */
-    sys_complete_promise_failure(key, value) {
+    sys_complete_promise_failure(key, value, options) {
         const ptr0 = passStringToWasm0(key, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_complete_promise_failure(this.__wbg_ptr, ptr0, len0, value);
+        const ret = wasm.wasmvm_sys_complete_promise_failure(this.__wbg_ptr, ptr0, len0, value, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -660,14 +622,15 @@ export class WasmVM {
     /**
      * @param {string} key
      * @param {Uint8Array} buffer
+     * @param {WasmPayloadOptions | null} [options]
      * @returns {number}
      */
-    sys_complete_promise_success(key, buffer) {
+    sys_complete_promise_success(key, buffer, options) {
         const ptr0 = passStringToWasm0(key, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
         const len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_complete_promise_success(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        const ret = wasm.wasmvm_sys_complete_promise_success(this.__wbg_ptr, ptr0, len0, ptr1, len1, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -739,12 +702,13 @@ export class WasmVM {
     }
     /**
      * @param {string} key
+     * @param {WasmPayloadOptions | null} [options]
      * @returns {number}
      */
-    sys_get_state(key) {
+    sys_get_state(key, options) {
         const ptr0 = passStringToWasm0(key, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_get_state(this.__wbg_ptr, ptr0, len0);
+        const ret = wasm.wasmvm_sys_get_state(this.__wbg_ptr, ptr0, len0, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -807,9 +771,10 @@ export class WasmVM {
      * @param {string | null} [scope]
      * @param {string | null} [limit_key]
      * @param {string | null} [name]
+     * @param {WasmPayloadOptions | null} [options]
      * @returns {WasmSendHandle}
      */
-    sys_send(service, handler, buffer, key, headers, delay, idempotency_key, scope, limit_key, name) {
+    sys_send(service, handler, buffer, key, headers, delay, idempotency_key, scope, limit_key, name, options) {
         const ptr0 = passStringToWasm0(service, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passStringToWasm0(handler, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
@@ -828,7 +793,7 @@ export class WasmVM {
         var len7 = WASM_VECTOR_LEN;
         var ptr8 = isLikeNone(name) ? 0 : passStringToWasm0(name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         var len8 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_send(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, !isLikeNone(delay), isLikeNone(delay) ? BigInt(0) : delay, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8);
+        const ret = wasm.wasmvm_sys_send(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, !isLikeNone(delay), isLikeNone(delay) ? BigInt(0) : delay, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
@@ -837,13 +802,14 @@ export class WasmVM {
     /**
      * @param {string} key
      * @param {Uint8Array} buffer
+     * @param {WasmPayloadOptions | null} [options]
      */
-    sys_set_state(key, buffer) {
+    sys_set_state(key, buffer, options) {
         const ptr0 = passStringToWasm0(key, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
         const len1 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_set_state(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        const ret = wasm.wasmvm_sys_set_state(this.__wbg_ptr, ptr0, len0, ptr1, len1, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
@@ -877,20 +843,22 @@ export class WasmVM {
     }
     /**
      * @param {WasmFailure} value
+     * @param {WasmPayloadOptions | null} [options]
      */
-    sys_write_output_failure(value) {
-        const ret = wasm.wasmvm_sys_write_output_failure(this.__wbg_ptr, value);
+    sys_write_output_failure(value, options) {
+        const ret = wasm.wasmvm_sys_write_output_failure(this.__wbg_ptr, value, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
     }
     /**
      * @param {Uint8Array} buffer
+     * @param {WasmPayloadOptions | null} [options]
      */
-    sys_write_output_success(buffer) {
+    sys_write_output_success(buffer, options) {
         const ptr0 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
         const len0 = WASM_VECTOR_LEN;
-        const ret = wasm.wasmvm_sys_write_output_success(this.__wbg_ptr, ptr0, len0);
+        const ret = wasm.wasmvm_sys_write_output_success(this.__wbg_ptr, ptr0, len0, isLikeNone(options) ? 0 : addToExternrefTable0(options));
         if (ret[1]) {
             throw takeFromExternrefTable0(ret[0]);
         }
@@ -911,7 +879,10 @@ export class WasmVM {
      */
     take_output() {
         const ret = wasm.wasmvm_take_output(this.__wbg_ptr);
-        return ret;
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return takeFromExternrefTable0(ret[0]);
     }
 }
 if (Symbol.dispose) WasmVM.prototype[Symbol.dispose] = WasmVM.prototype.free;
@@ -925,11 +896,112 @@ export function cancel_handle() {
 }
 
 /**
- * This will set the log level of the overall log subscriber.
+ * Decodes a buffer of service protocol frames, as passed to `WasmVM.notify_input` or returned by `WasmVM.take_output`.
+ *
+ * Commands are numbered starting from `first_command_index`, defaulting to 0.
+ * @param {Uint8Array} buffer
+ * @param {number | null} [first_command_index]
+ * @returns {WasmProtocolMessage[]}
+ */
+export function decode_protocol_messages(buffer, first_command_index) {
+    const ptr0 = passArray8ToWasm0(buffer, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ret = wasm.decode_protocol_messages(ptr0, len0, isLikeNone(first_command_index) ? Number.MAX_SAFE_INTEGER : (first_command_index) >>> 0);
+    if (ret[3]) {
+        throw takeFromExternrefTable0(ret[2]);
+    }
+    var v2 = getArrayJsValueFromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v2;
+}
+
+/**
+ * Compares the commands of two journals, returning the first diverging command, or `None` if they match.
+ *
+ * Journals are lists of protocol messages, as returned by `decode_protocol_messages`,
+ * for example decoded from the inputs and outputs of a trace recorded with `WasmVMOptions.record_trace`.
+ * Non-command messages are ignored.
+ * @param {WasmProtocolMessage[]} expected
+ * @param {WasmProtocolMessage[]} actual
+ * @returns {WasmJournalDiff | undefined}
+ */
+export function diff_journals(expected, actual) {
+    const ptr0 = passArrayJsValueToWasm0(expected, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayJsValueToWasm0(actual, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ret = wasm.diff_journals(ptr0, len0, ptr1, len1);
+    return ret;
+}
+
+/**
+ * Encodes the given messages as service protocol frames, the inverse of [`decode_protocol_messages`].
+ * @param {WasmProtocolMessage[]} messages
+ * @returns {Uint8Array}
+ */
+export function encode_protocol_messages(messages) {
+    const ptr0 = passArrayJsValueToWasm0(messages, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ret = wasm.encode_protocol_messages(ptr0, len0);
+    if (ret[3]) {
+        throw takeFromExternrefTable0(ret[2]);
+    }
+    var v2 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+    return v2;
+}
+
+/**
+ * Returns true if the runtime will retry the invocation after the failure.
+ *
+ * Suspensions, closed input streams and terminal failures are not retried.
+ * @param {WasmFailure} failure
+ * @returns {boolean}
+ */
+export function is_retryable(failure) {
+    const ret = wasm.is_retryable(failure);
+    return ret !== 0;
+}
+
+/**
+ * Returns true if the failure is caused by the invocation being suspended.
+ * In this case the handler should stop executing, without reporting the failure.
+ * @param {WasmFailure} failure
+ * @returns {boolean}
+ */
+export function is_suspended(failure) {
+    const ret = wasm.is_suspended(failure);
+    return ret !== 0;
+}
+
+/**
+ * Replays a trace recorded with [`WasmVMOptions::record_trace`] on a fresh VM, checking its outputs match the recorded ones.
+ *
+ * Replay stops at the first output mismatch. Truncated traces are replayed up to the last recorded event.
+ * @param {string} trace
+ * @param {LogLevel} log_level
+ * @param {number} logger_id
+ * @returns {WasmTraceReplayResult}
+ */
+export function replay_invocation_trace(trace, log_level, logger_id) {
+    const ptr0 = passStringToWasm0(trace, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ret = wasm.replay_invocation_trace(ptr0, len0, log_level, logger_id);
+    if (ret[2]) {
+        throw takeFromExternrefTable0(ret[1]);
+    }
+    return takeFromExternrefTable0(ret[0]);
+}
+
+/**
+ * This will set the log level and format of the overall log subscriber.
+ *
+ * Can be invoked several times, the latest invocation wins.
  * @param {LogLevel} level
+ * @param {LogFormat | null} [format]
  */
-export function set_log_level(level) {
-    wasm.set_log_level(level);
+export function set_log_level(level, format) {
+    wasm.set_log_level(level, isLikeNone(format) ? 0 : addToExternrefTable0(format));
 }
 
 /**
@@ -1041,9 +1113,6 @@ export function __wbg_entries_564a7e8b1e54ede5(arg0) {
     const ret = Object.entries(arg0);
     return ret;
 }
-export function __wbg_fatal_dd3314b57e7713f4(arg0, arg1) {
-    fatal(getStringFromWasm0(arg0, arg1));
-}
 export function __wbg_from_fa561fa561dc8031(arg0) {
     const ret = Array.from(arg0);
     return ret;
@@ -1081,6 +1150,16 @@ export function __wbg_instanceof_ArrayBuffer_53db37b06f6b9afe(arg0) {
     const ret = result;
     return ret;
 }
+export function __wbg_instanceof_Map_16f217b9a2a08d8c(arg0) {
+    let result;
+    try {
+        result = arg0 instanceof Map;
+    } catch (_) {
+        result = false;
+    }
+    const ret = result;
+    return ret;
+}
 export function __wbg_instanceof_Uint8Array_abd07d4bd221d50b(arg0) {
     let result;
     try {
@@ -1119,6 +1198,10 @@ export function __wbg_new_02d162bc6cf02f60() {
     const ret = new Object();
     return ret;
 }
+export function __wbg_new_070df68d66325372() {
+    const ret = new Map();
+    return ret;
+}
 export function __wbg_new_0_2722fcdb71a888a6() {
     const ret = new Date();
     return ret;
@@ -1155,6 +1238,10 @@ export function __wbg_now_81363d44c96dd239() {
     const ret = Date.now();
     return ret;
 }
+export function __wbg_now_d682e7d5ce7200e2() {
+    const ret = performance.now();
+    return ret;
+}
 export function __wbg_process_44c7a14e11e9f69e(arg0) {
     const ret = arg0.process;
     return ret;
@@ -1175,6 +1262,10 @@ export function __wbg_set_6be42768c690e380(arg0, arg1, arg2) {
 export function __wbg_set_78ea6a19f4818587(arg0, arg1, arg2) {
     arg0[arg1 >>> 0] = arg2;
 }
+export function __wbg_set_facb7a5914e0fa39(arg0, arg1, arg2) {
+    const ret = arg0.set(arg1, arg2);
+    return ret;
+}
 export function __wbg_static_accessor_GLOBAL_THIS_02344c9b09eb08a9() {
     const ret = typeof globalThis === 'undefined' ? null : globalThis;
     return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
@@ -1203,8 +1294,16 @@ export function __wbg_versions_276b2795b1c6a219(arg0) {
     const ret = arg0.versions;
     return ret;
 }
-export function __wbg_vm_log_702db15d7203ea28(arg0, arg1, arg2, arg3) {
-    vm_log(arg0, getArrayU8FromWasm0(arg1, arg2), arg3 === Number.MAX_SAFE_INTEGER ? undefined : arg3);
+export function __wbg_vm_log_568f95908214b96c(arg0, arg1, arg2, arg3, arg4) {
+    vm_log(arg0, getArrayU8FromWasm0(arg1, arg2), arg3 === Number.MAX_SAFE_INTEGER ? undefined : arg3, arg4);
+}
+export function __wbg_vm_log_batch_d73acc916524f682(arg0, arg1, arg2) {
+    var v0 = getArrayJsValueFromWasm0(arg0, arg1).slice();
+    wasm.__wbindgen_free(arg0, arg1 * 4, 4);
+    vm_log_batch(v0, arg2 === Number.MAX_SAFE_INTEGER ? undefined : arg2);
+}
+export function __wbg_vm_span_event_610a4e2ba3c98ae1(arg0, arg1) {
+    vm_span_event(arg0, arg1 === Number.MAX_SAFE_INTEGER ? undefined : arg1);
 }
 export function __wbg_wasmheader_new(arg0) {
     const ret = WasmHeader.__wrap(arg0);
@@ -1219,17 +1318,22 @@ export function __wbindgen_cast_0000000000000001(arg0) {
     const ret = arg0;
     return ret;
 }
-export function __wbindgen_cast_0000000000000002(arg0, arg1) {
+export function __wbindgen_cast_0000000000000002(arg0) {
+    // Cast intrinsic for `I64 -> Externref`.
+    const ret = arg0;
+    return ret;
+}
+export function __wbindgen_cast_0000000000000003(arg0, arg1) {
     // Cast intrinsic for `Ref(Slice(U8)) -> NamedExternref("Uint8Array")`.
     const ret = getArrayU8FromWasm0(arg0, arg1);
     return ret;
 }
-export function __wbindgen_cast_0000000000000003(arg0, arg1) {
+export function __wbindgen_cast_0000000000000004(arg0, arg1) {
     // Cast intrinsic for `Ref(String) -> Externref`.
     const ret = getStringFromWasm0(arg0, arg1);
     return ret;
 }
-export function __wbindgen_cast_0000000000000004(arg0) {
+export function __wbindgen_cast_0000000000000005(arg0) {
     // Cast intrinsic for `U64 -> Externref`.
     const ret = BigInt.asUintN(64, arg0);
     return ret;
@@ -18,64 +18,67 @@ export const __wbg_wasminput_free: (a: number, b: number) => void;
 export const __wbg_wasmresponsehead_free: (a: number, b: number) => void;
 export const __wbg_wasmvm_free: (a: number, b: number) => void;
 export const cancel_handle: () => number;
-export const set_log_level: (a: number) => void;
+export const set_log_level: (a: number, b: number) => void;
 export const wasmheader_new: (a: number, b: number, c: number, d: number) => number;
 export const wasmidentityverifier_new: (a: number, b: number) => [number, number, number];
 export const wasmidentityverifier_verify_identity: (a: number, b: number, c: number, d: number, e: number) => [number, number];
 export const wasmvm_do_progress: (a: number, b: any) => [number, number, number];
 export const wasmvm_get_response_head: (a: number) => number;
-export const wasmvm_is_completed: (a: number, b: number) => number;
+export const wasmvm_invocation_trace: (a: number) => [number, number, number, number];
+export const wasmvm_is_completed: (a: number, b: number) => [number, number, number];
 export const wasmvm_is_processing: (a: number) => number;
 export const wasmvm_is_ready_to_execute: (a: number) => [number, number, number];
-export const wasmvm_last_command_index: (a: number) => number;
-export const wasmvm_new: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number];
-export const wasmvm_notify_error: (a: number, b: number, c: number, d: number, e: number) => void;
-export const wasmvm_notify_error_for_next_command: (a: number, b: number, c: number, d: number, e: number, f: number) => void;
-export const wasmvm_notify_error_for_specific_command: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number) => void;
-export const wasmvm_notify_error_with_delay_override: (a: number, b: number, c: number, d: number, e: number, f: number, g: bigint) => void;
-export const wasmvm_notify_input: (a: number, b: number, c: number) => void;
-export const wasmvm_notify_input_closed: (a: number) => void;
-export const wasmvm_propose_run_completion_failure: (a: number, b: number, c: any) => [number, number];
-export const wasmvm_propose_run_completion_failure_transient: (a: number, b: number, c: number, d: number, e: number, f: number, g: bigint, h: number) => [number, number];
-export const wasmvm_propose_run_completion_failure_transient_with_delay_override: (a: number, b: number, c: number, d: number, e: number, f: number, g: bigint, h: number, i: bigint, j: number, k: number, l: bigint) => [number, number];
-export const wasmvm_propose_run_completion_failure_transient_with_pause: (a: number, b: number, c: number, d: number, e: number, f: number, g: bigint) => [number, number];
-export const wasmvm_propose_run_completion_success: (a: number, b: number, c: number, d: number) => [number, number];
+export const wasmvm_last_command_index: (a: number) => [number, number, number];
+export const wasmvm_new: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number) => [number, number, number];
+export const wasmvm_notify_error: (a: number, b: any) => [number, number];
+export const wasmvm_notify_input: (a: number, b: number, c: number) => [number, number];
+export const wasmvm_notify_input_closed: (a: number) => [number, number];
+export const wasmvm_notify_pause: (a: number, b: number, c: number, d: number, e: number) => [number, number];
+export const wasmvm_propose_run_completion: (a: number, b: number, c: any, d: number) => [number, number];
+export const wasmvm_set_log_level: (a: number, b: number) => void;
+export const wasmvm_stats: (a: number) => any;
 export const wasmvm_sys_attach_invocation: (a: number, b: number, c: number) => [number, number, number];
 export const wasmvm_sys_awakeable: (a: number) => [number, number, number];
-export const wasmvm_sys_call: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number) => [number, number, number];
+export const wasmvm_sys_call: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number) => [number, number, number];
 export const wasmvm_sys_cancel_invocation: (a: number, b: number, c: number) => [number, number];
 export const wasmvm_sys_clear_all_state: (a: number) => [number, number];
 export const wasmvm_sys_clear_state: (a: number, b: number, c: number) => [number, number];
-export const wasmvm_sys_complete_awakeable_failure: (a: number, b: number, c: number, d: any) => [number, number];
-export const wasmvm_sys_complete_awakeable_success: (a: number, b: number, c: number, d: number, e: number) => [number, number];
-export const wasmvm_sys_complete_promise_failure: (a: number, b: number, c: number, d: any) => [number, number, number];
-export const wasmvm_sys_complete_promise_success: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
+export const wasmvm_sys_complete_awakeable_failure: (a: number, b: number, c: number, d: any, e: number) => [number, number];
+export const wasmvm_sys_complete_awakeable_success: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number];
+export const wasmvm_sys_complete_promise_failure: (a: number, b: number, c: number, d: any, e: number) => [number, number, number];
+export const wasmvm_sys_complete_promise_success: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number, number];
 export const wasmvm_sys_complete_signal_failure: (a: number, b: number, c: number, d: number, e: number, f: any) => [number, number];
 export const wasmvm_sys_complete_signal_success: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number];
 export const wasmvm_sys_end: (a: number) => [number, number];
 export const wasmvm_sys_get_invocation_output: (a: number, b: number, c: number) => [number, number, number];
 export const wasmvm_sys_get_promise: (a: number, b: number, c: number) => [number, number, number];
-export const wasmvm_sys_get_state: (a: number, b: number, c: number) => [number, number, number];
+export const wasmvm_sys_get_state: (a: number, b: number, c: number, d: number) => [number, number, number];
 export const wasmvm_sys_get_state_keys: (a: number) => [number, number, number];
 export const wasmvm_sys_input: (a: number) => [number, number, number];
 export const wasmvm_sys_peek_promise: (a: number, b: number, c: number) => [number, number, number];
 export const wasmvm_sys_run: (a: number, b: number, c: number) => [number, number, number];
-export const wasmvm_sys_send: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: bigint, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number) => [number, number, number];
-export const wasmvm_sys_set_state: (a: number, b: number, c: number, d: number, e: number) => [number, number];
+export const wasmvm_sys_send: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: bigint, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number) => [number, number, number];
+export const wasmvm_sys_set_state: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number];
 export const wasmvm_sys_signal: (a: number, b: number, c: number) => [number, number, number];
 export const wasmvm_sys_sleep: (a: number, b: bigint, c: number, d: number) => [number, number, number];
-export const wasmvm_sys_write_output_failure: (a: number, b: any) => [number, number];
-export const wasmvm_sys_write_output_success: (a: number, b: number, c: number) => [number, number];
+export const wasmvm_sys_write_output_failure: (a: number, b: any, c: number) => [number, number];
+export const wasmvm_sys_write_output_success: (a: number, b: number, c: number, d: number) => [number, number];
 export const wasmvm_take_notification: (a: number, b: number) => [number, number, number];
-export const wasmvm_take_output: (a: number) => any;
+export const wasmvm_take_output: (a: number) => [number, number, number];
 export const __wbg_get_wasminput_invocation_id: (a: number) => [number, number];
 export const start: () => void;
+export const replay_invocation_trace: (a: number, b: number, c: number, d: number) => [number, number, number];
+export const is_retryable: (a: any) => number;
+export const is_suspended: (a: any) => number;
+export const decode_protocol_messages: (a: number, b: number, c: number) => [number, number, number, number];
+export const diff_journals: (a: number, b: number, c: number, d: number) => any;
+export const encode_protocol_messages: (a: number, b: number) => [number, number, number, number];
 export const __wbindgen_malloc: (a: number, b: number) => number;
 export const __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
 export const __wbindgen_exn_store: (a: number) => void;
 export const __externref_table_alloc: () => number;
 export const __wbindgen_externrefs: WebAssembly.Table;
-export const __wbindgen_free: (a: number, b: number, c: number) => void;
 export const __externref_drop_slice: (a: number, b: number) => void;
+export const __wbindgen_free: (a: number, b: number, c: number) => void;
 export const __externref_table_dealloc: (a: number) => void;
 export const __wbindgen_start: () => void;
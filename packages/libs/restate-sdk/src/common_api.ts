@@ -88,6 +88,8 @@ export type {
   ObjectOptions,
   WorkflowOptions,
   RetryPolicy,
+  CoreOptions,
+  CoreSpanEvent,
 } from "./types/rpc.js";
 export {
   service,
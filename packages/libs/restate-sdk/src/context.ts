@@ -202,14 +202,16 @@ export type RunOptions<T> = {
   /**
    * Max number of attempts (including the initial), before giving up.
    *
-   * When giving up, `ctx.run` will throw a `TerminalError` wrapping the original error message.
+   * When giving up, `ctx.run` will throw a `TerminalError` wrapping the original error message,
+   * unless `pauseOnMaxRetries` is set.
    */
   maxRetryAttempts?: number;
 
   /**
    * Max duration of retries, before giving up.
    *
-   * When giving up, `ctx.run` will throw a `TerminalError` wrapping the original error message.
+   * When giving up, `ctx.run` will throw a `TerminalError` wrapping the original error message,
+   * unless `pauseOnMaxRetries` is set.
    *
    * If a number is provided, it will be interpreted as milliseconds.
    */
@@ -241,6 +243,14 @@ export type RunOptions<T> = {
    * The default value is `2`, meaning retry interval will double at each attempt.
    */
   retryIntervalFactor?: number;
+
+  /**
+   * When giving up after `maxRetryAttempts` or `maxRetryDuration`, pause the invocation instead of failing `ctx.run`.
+   * The invocation can then be resumed manually, e.g. after fixing the external dependency.
+   *
+   * The default is `false`.
+   */
+  pauseOnMaxRetries?: boolean;
 };
 
 /**
@@ -555,40 +555,67 @@ export class ContextImpl
           err = ensureError(e, this.asTerminalError);
         }
         const attemptDuration = Date.now() - startTime;
+        const onMaxAttempts: vm.WasmOnMaxAttempts = options?.pauseOnMaxRetries
+          ? "Pause"
+          : "FailAsTerminal";
 
         // Propose the completion to the VM
         try {
           if (err !== undefined) {
             if (err instanceof TerminalError) {
               // Record failure, go ahead
-              this.coreVm.propose_run_completion_failure(handle, {
-                code: err.code,
-                message: err.message,
-                metadata: Object.entries(err.metadata ?? {}).map(
-                  ([key, value]) => ({ key, value })
-                ),
+              this.coreVm.propose_run_completion(handle, {
+                TerminalFailure: {
+                  code: err.code,
+                  message: err.message,
+                  metadata: Object.entries(err.metadata ?? {}).map(
+                    ([key, value]) => ({ key, value })
+                  ),
+                },
               });
             } else if (err instanceof RetryableError) {
-              this.coreVm.propose_run_completion_failure_transient_with_delay_override(
-                handle,
-                err.message,
-                err.stack,
-                BigInt(attemptDuration),
-                err.retryAfter !== undefined
-                  ? BigInt(millisOrDurationToMillis(err.retryAfter))
-                  : undefined,
-                options?.maxRetryAttempts,
+              // Fixed delay if any override is set, otherwise the runtime policy.
+              let retryPolicy: vm.WasmRetryPolicy = "Infinite";
+              if (
+                err.retryAfter !== undefined ||
+                options?.maxRetryAttempts !== undefined ||
                 options?.maxRetryDuration !== undefined
-                  ? BigInt(millisOrDurationToMillis(options?.maxRetryDuration))
-                  : undefined
-              );
-            } else if (err instanceof PauseError) {
-              this.coreVm.propose_run_completion_failure_transient_with_pause(
+              ) {
+                retryPolicy = {
+                  FixedDelay: {
+                    interval:
+                      err.retryAfter !== undefined
+                        ? millisOrDurationToMillis(err.retryAfter)
+                        : undefined,
+                    max_attempts: options?.maxRetryAttempts,
+                    max_duration:
+                      options?.maxRetryDuration !== undefined
+                        ? millisOrDurationToMillis(options?.maxRetryDuration)
+                        : undefined,
+                    on_max_attempts: onMaxAttempts,
+                  },
+                };
+              }
+              this.coreVm.propose_run_completion(
                 handle,
-                err.message,
-                err.stack,
-                BigInt(attemptDuration)
+                {
+                  RetryableFailure: {
+                    error_message: err.message,
+                    error_stacktrace: err.stack,
+                    attempt_duration: attemptDuration,
+                  },
+                },
+                retryPolicy
               );
+            } else if (err instanceof PauseError) {
+              this.coreVm.propose_run_completion(handle, {
+                RetryableFailure: {
+                  error_message: err.message,
+                  error_stacktrace: err.stack,
+                  attempt_duration: attemptDuration,
+                  should_pause: true,
+                },
+              });
             } else {
               this.vmLogger.warn(
                 `Error when processing ctx.run '${name}'.\n`,
@@ -596,7 +623,7 @@ export class ContextImpl
               );
 
               // Configure the retry policy if any of the parameters are set.
-              let retryPolicy;
+              let retryPolicy: vm.WasmRetryPolicy | undefined;
               if (
                 options?.retryIntervalFactor !== undefined ||
                 options?.maxRetryAttempts !== undefined ||
@@ -605,25 +632,32 @@ export class ContextImpl
                 options?.maxRetryInterval !== undefined
               ) {
                 retryPolicy = {
-                  factor: options?.retryIntervalFactor ?? 2.0,
-                  initial_interval: millisOrDurationToMillis(
-                    options?.initialRetryInterval ?? 50
-                  ),
-                  max_attempts: options?.maxRetryAttempts,
-                  max_duration:
-                    options?.maxRetryDuration === undefined
-                      ? undefined
-                      : millisOrDurationToMillis(options?.maxRetryDuration),
-                  max_interval: millisOrDurationToMillis(
-                    options?.maxRetryInterval ?? { seconds: 10 }
-                  ),
+                  Exponential: {
+                    factor: options?.retryIntervalFactor ?? 2.0,
+                    initial_interval: millisOrDurationToMillis(
+                      options?.initialRetryInterval ?? 50
+                    ),
+                    max_attempts: options?.maxRetryAttempts,
+                    max_duration:
+                      options?.maxRetryDuration === undefined
+                        ? undefined
+                        : millisOrDurationToMillis(options?.maxRetryDuration),
+                    max_interval: millisOrDurationToMillis(
+                      options?.maxRetryInterval ?? { seconds: 10 }
+                    ),
+                    on_max_attempts: onMaxAttempts,
+                  },
                 };
               }
-              this.coreVm.propose_run_completion_failure_transient(
+              this.coreVm.propose_run_completion(
                 handle,
-                err.message,
-                err.stack,
-                BigInt(attemptDuration),
+                {
+                  RetryableFailure: {
+                    error_message: err.message,
+                    error_stacktrace: err.stack,
+                    attempt_duration: attemptDuration,
+                  },
+                },
                 retryPolicy
               );
             }
@@ -632,7 +666,9 @@ export class ContextImpl
             // @ts-expect-error
             const serializedRes = serde.serialize(res);
             const encodedRes = this.journalValueCodec.encode(serializedRes);
-            this.coreVm.propose_run_completion_success(handle, encodedRes);
+            this.coreVm.propose_run_completion(handle, {
+              Success: encodedRes,
+            });
           }
         } catch (e) {
           this.abortAttempt(e);
@@ -1083,9 +1119,9 @@ type Completer = (
 // process() can call the right VM notification method.
 //
 // - Preparation failure (command not yet in journal): new CommandError(e, type)
-//   → notify_error_for_next_command
+//   → notify_error with a Next related command
 // - Completion failure (command exists): new CommandError(e, type, index)
-//   → notify_error_for_specific_command
+//   → notify_error with a Specific related command
 export class CommandError extends Error {
   constructor(cause: unknown, commandType: WasmCommandType);
   constructor(
@@ -14,6 +14,7 @@
 import type * as d from "./discovery.js";
 import type { ContextImpl } from "../context_impl.js";
 import type {
+  CoreOptions,
   HandlerWrapper,
   ObjectOptions,
   ServiceHandlerOpts,
@@ -48,6 +49,7 @@ export interface ExecutionOptions {
   hooks?: HooksProvider[];
   explicitCancellation?: boolean;
   onJournalMismatchErrors?: "retry" | "pause" | "fail";
+  coreOptions?: CoreOptions;
 }
 
 export interface ComponentHandler {
@@ -146,6 +148,10 @@ function createExecutionOptions(
     onJournalMismatchErrors:
       handlerOptions?.onJournalMismatchErrors ??
       serviceOptions?.onJournalMismatchErrors,
+    coreOptions:
+      serviceOptions?.coreOptions || handlerOptions?.coreOptions
+        ? { ...serviceOptions?.coreOptions, ...handlerOptions?.coreOptions }
+        : undefined,
   };
 }
 
@@ -1,5 +1,9 @@
-import { Logger } from "../../logging/logger.js";
-import { LogSource, RestateLogLevel } from "../../logging/logger_transport.js";
+import {
+  LoggerContext,
+  type LoggerTransport,
+  LogSource,
+  RestateLogLevel,
+} from "../../logging/logger_transport.js";
 import * as vm from "./vm/sdk_shared_core_wasm_bindings.js";
 import { defaultLoggerTransport } from "../../logging/console_logger_transport.js";
 
@@ -7,33 +11,44 @@ import { defaultLoggerTransport } from "../../logging/console_logger_transport.j
  * This file contains the shared state used to install a logger that the shared-core uses for logging.
  */
 
-const invocationLoggers: Map<number, Logger> = new Map<number, Logger>();
+type InvocationLogger = {
+  loggerTransport: LoggerTransport;
+  context?: LoggerContext;
+};
+
+const invocationLoggers: Map<number, InvocationLogger> = new Map<
+  number,
+  InvocationLogger
+>();
+const spanListeners: Map<number, (event: unknown) => void> = new Map<
+  number,
+  (event: unknown) => void
+>();
 const logsTextDecoder = new TextDecoder("utf-8", { fatal: false });
 
 /**
  * The shared core propagates logs to the SDK invoking this method.
  * When possible, it provides an invocationId, which is used to access the registered invocationLoggers, that should contain the logger per invocation id.
+ * The context describes the state of the VM when the record was emitted, and it's forwarded to the logger.
  */
 export function vm_log(
   level: vm.LogLevel,
   strBytes: Uint8Array,
-  loggerId?: number
+  loggerId?: number,
+  context?: vm.WasmLogContext
 ) {
   try {
     const logger = (loggerId && invocationLoggers.get(loggerId)) || undefined;
     const str = logsTextDecoder.decode(strBytes);
-    if (logger !== undefined) {
-      logger.logForLevel(wasmLogLevelToRestateLogLevel(level), str);
-    } else {
-      defaultLoggerTransport(
-        {
-          level: wasmLogLevelToRestateLogLevel(level),
-          replaying: false,
-          source: LogSource.JOURNAL,
-        },
-        str
-      );
-    }
+    (logger?.loggerTransport ?? defaultLoggerTransport)(
+      {
+        level: wasmLogLevelToRestateLogLevel(level),
+        replaying: context?.state === "Replaying",
+        source: LogSource.JOURNAL,
+        context: withVmLogContext(logger?.context, context),
+      },
+      str
+    );
   } catch (e) {
     // This function CAN'T EVER propagate an error,
     // because otherwise it will cause an awesome error in the shared core due to concurrent usage of it.
@@ -48,22 +63,86 @@ export function vm_log(
   }
 }
 
+/**
+ * Same as vm_log, but invoked with a batch of log records when the shared core buffers logs.
+ */
+export function vm_log_batch(
+  records: {
+    level: vm.LogLevel;
+    message: Uint8Array;
+    context?: vm.WasmLogContext;
+  }[],
+  loggerId?: number
+) {
+  for (const record of records) {
+    vm_log(record.level, record.message, loggerId, record.context);
+  }
+}
+
+/**
+ * The shared core propagates span open/record/close events invoking this method, when span export is enabled.
+ */
+export function vm_span_event(event: unknown, loggerId?: number) {
+  try {
+    const listener = loggerId && spanListeners.get(loggerId);
+    if (listener) {
+      listener(event);
+    }
+  } catch {
+    // Same as vm_log, this function CAN'T EVER propagate an error.
+  }
+}
+
 /**
  * Invoked by the shared core's panic hook when it hits a rust panic.
  */
-export function fatal(message: string) {
+export function fatal(report: {
+  message: string;
+  location?: string;
+  logger_id?: number;
+  invocation_id?: string;
+  command_index?: number;
+  recoverable: boolean;
+}) {
   try {
     const stack = new Error().stack;
-    defaultLoggerTransport(
-      {
-        level: RestateLogLevel.ERROR,
-        replaying: false,
-        source: LogSource.SYSTEM,
-      },
-      "FATAL: the SDK shared-core panicked, terminating the process. Reason: \n" +
-        message +
-        (stack ? "\n\nStack:\n" + stack : "")
-    );
+    const message =
+      (report.recoverable
+        ? "The SDK shared-core panicked, failing the invocation. Reason: \n"
+        : "FATAL: the SDK shared-core panicked, terminating the process. Reason: \n") +
+      report.message +
+      (report.location ? "\nLocation: " + report.location : "") +
+      (report.invocation_id ? "\nInvocation id: " + report.invocation_id : "") +
+      (report.command_index !== undefined
+        ? "\nLast command index: " + report.command_index
+        : "") +
+      (stack ? "\n\nStack:\n" + stack : "");
+    const logger =
+      (report.logger_id && invocationLoggers.get(report.logger_id)) ||
+      undefined;
+    if (logger !== undefined) {
+      logger.loggerTransport(
+        {
+          level: RestateLogLevel.ERROR,
+          replaying: false,
+          source: LogSource.JOURNAL,
+          context: logger.context,
+        },
+        message
+      );
+    } else {
+      defaultLoggerTransport(
+        {
+          level: RestateLogLevel.ERROR,
+          replaying: false,
+          source: LogSource.SYSTEM,
+        },
+        message
+      );
+    }
+    if (report.recoverable) {
+      return;
+    }
 
     const proc = (
       globalThis as typeof globalThis & {
@@ -78,12 +157,51 @@ export function fatal(message: string) {
   }
 }
 
-export function registerLogger(loggerId: number, logger: Logger) {
-  invocationLoggers.set(loggerId, logger);
+export function registerLogger(
+  loggerId: number,
+  loggerTransport: LoggerTransport,
+  context?: LoggerContext
+) {
+  invocationLoggers.set(loggerId, { loggerTransport, context });
 }
 
 export function destroyLogger(loggerId: number) {
   invocationLoggers.delete(loggerId);
+  spanListeners.delete(loggerId);
+}
+
+export function registerSpanListener(
+  loggerId: number,
+  listener: (event: unknown) => void
+) {
+  spanListeners.set(loggerId, listener);
+}
+
+/**
+ * Adds the command index of the VM log context to the invocation logger context.
+ */
+function withVmLogContext(
+  context?: LoggerContext,
+  vmContext?: vm.WasmLogContext
+): LoggerContext | undefined {
+  if (
+    context === undefined ||
+    vmContext === undefined ||
+    vmContext.command_index < 0
+  ) {
+    return context;
+  }
+  return new LoggerContext(
+    context.invocationId,
+    context.serviceName,
+    context.handlerName,
+    context.key,
+    context.request,
+    {
+      ...context.additionalContext,
+      commandIndex: vmContext.command_index.toString(),
+    }
+  );
 }
 
 function wasmLogLevelToRestateLogLevel(level: vm.LogLevel): RestateLogLevel {
@@ -33,6 +33,7 @@ import { restoreError, sanitizeError } from "../../error_sanitization.js";
 import type { InvocationId, Request } from "../../context.js";
 import * as vm from "./vm/sdk_shared_core_wasm_bindings.js";
 import { HandlerKind } from "../../types/rpc.js";
+import type { CoreOptions, CoreSpanEvent } from "../../types/rpc.js";
 import { createLogger, type Logger } from "../../logging/logger.js";
 import { DEFAULT_CONSOLE_LOGGER_LOG_LEVEL } from "../../logging/console_logger_transport.js";
 import {
@@ -64,7 +65,11 @@ import {
   simpleResponse,
   tryCreateContextualLogger,
 } from "./utils.js";
-import { destroyLogger, registerLogger } from "./core_logging.js";
+import {
+  destroyLogger,
+  registerLogger,
+  registerSpanListener,
+} from "./core_logging.js";
 import type { Hooks } from "../../hooks.js";
 
 // Hidden symbol key used by first-party hooks to read the live
@@ -271,6 +276,7 @@ class RestateInvokeResponse implements RestateResponse {
 
   private readonly loggerId: number;
   private vmLogger: Logger;
+  private readonly vmLoggerContext: LoggerContext;
   private readonly coreVm: vm.WasmVM;
 
   constructor(
@@ -294,6 +300,12 @@ class RestateInvokeResponse implements RestateResponse {
         ([k, v]) =>
           new vm.WasmHeader(k, v instanceof Array ? v[0]! : (v as string))
       );
+    const spanListener = handler.executionOptions.coreOptions?.spanListener;
+    if (spanListener !== undefined) {
+      registerSpanListener(this.loggerId, (event) =>
+        spanListener(event as CoreSpanEvent)
+      );
+    }
     this.coreVm = new vm.WasmVM(
       vmHeaders,
       restateLogLevelToWasmLogLevel(DEFAULT_CONSOLE_LOGGER_LOG_LEVEL),
@@ -302,7 +314,8 @@ class RestateInvokeResponse implements RestateResponse {
       handler.executionOptions.explicitCancellation ?? false,
       onJournalMismatchErrorsToWasm(
         handler.executionOptions.onJournalMismatchErrors
-      )
+      ),
+      coreOptionsToWasm(handler.executionOptions.coreOptions)
     );
     const responseHead = this.coreVm.get_response_head();
     this.statusCode = responseHead.status_code;
@@ -315,17 +328,18 @@ class RestateInvokeResponse implements RestateResponse {
         "x-restate-server": X_RESTATE_SERVER,
       }
     );
+    this.vmLoggerContext = new LoggerContext(
+      invocationIdFromHeaders(this.attemptHeaders),
+      this.service.name(),
+      this.handler.name(),
+      undefined,
+      undefined,
+      this.additionalContext
+    );
     this.vmLogger = createLogger(
       this.loggerTransport,
       LogSource.JOURNAL,
-      new LoggerContext(
-        invocationIdFromHeaders(this.attemptHeaders),
-        this.service.name(),
-        this.handler.name(),
-        undefined,
-        undefined,
-        this.additionalContext
-      )
+      this.vmLoggerContext
     );
   }
 
@@ -357,7 +371,7 @@ class RestateInvokeResponse implements RestateResponse {
         // fails on the next VM call (e.g. ctx.run, ctx.sleep).
         setImmediate(() => {
           const msg = "Connection closed";
-          this.coreVm.notify_error(msg, msg);
+          this.coreVm.notify_error({ message: msg, stacktrace: msg });
         });
       },
       { once: true }
@@ -365,7 +379,11 @@ class RestateInvokeResponse implements RestateResponse {
     // Use a default logger that still respects the endpoint custom logger
     // We will override this later with a logger that has a LoggerContext
     // See vm_log below for more details
-    registerLogger(this.loggerId, this.vmLogger);
+    registerLogger(
+      this.loggerId,
+      this.loggerTransport,
+      this.vmLoggerContext
+    );
 
     const journalValueCodec: JournalValueCodec = this.journalValueCodecInit
       ? await this.journalValueCodecInit
@@ -449,7 +467,7 @@ class RestateInvokeResponse implements RestateResponse {
       );
 
       // See vm_log below for more details
-      registerLogger(this.loggerId, this.vmLogger);
+      registerLogger(this.loggerId, this.loggerTransport, loggerContext);
       if (!this.coreVm.is_processing()) {
         this.vmLogger.info("Replaying invocation.");
       } else {
@@ -474,7 +492,10 @@ class RestateInvokeResponse implements RestateResponse {
       // That's "preflight" failure cases, where stuff fails before running user code
       // In this scenario, we close the coreVm, then flush and close
       const error = ensureError(e);
-      this.coreVm.notify_error(error.message, error.message);
+      this.coreVm.notify_error({
+        message: error.message,
+        stacktrace: error.message,
+      });
       try {
         await flushAndClose(
           this.coreVm,
@@ -483,6 +504,7 @@ class RestateInvokeResponse implements RestateResponse {
           outputWriter
         );
       } finally {
+        this.deliverInvocationTrace();
         // Safety net: the abort listener should clean this up when the attempt
         // ends, but process() owns the logger registration, so avoid leaking if
         // an adapter ever fails to abort the attempt-completed signal.
@@ -515,6 +537,7 @@ class RestateInvokeResponse implements RestateResponse {
           outputWriter
         );
       } finally {
+        this.deliverInvocationTrace();
         // Safety net: the abort listener should clean this up when the attempt
         // ends, but process() owns the logger registration, so avoid leaking if
         // an adapter ever fails to abort the attempt-completed signal.
@@ -522,6 +545,21 @@ class RestateInvokeResponse implements RestateResponse {
       }
     }
   }
+
+  private deliverInvocationTrace() {
+    const recordTrace = this.handler.executionOptions.coreOptions?.recordTrace;
+    if (recordTrace === undefined) {
+      return;
+    }
+    try {
+      const trace = this.coreVm.invocation_trace();
+      if (trace !== undefined) {
+        recordTrace.listener(trace);
+      }
+    } catch (e) {
+      this.vmLogger.warn("Cannot deliver the invocation trace.\n", e);
+    }
+  }
 }
 
 async function bufferJournalReplayInCoreVm(
@@ -622,20 +660,23 @@ function notifyError(
     logError(ctx.vmLogger, cause);
     if (e.hasCommandIndex) {
       // Completion failure — command exists in the journal
-      ctx.coreVm.notify_error_for_specific_command(
-        cause.message,
-        cause.stack,
-        e.commandType,
-        e.commandIndex!,
-        null
-      );
+      ctx.coreVm.notify_error({
+        message: cause.message,
+        stacktrace: cause.stack,
+        related_command: {
+          Specific: {
+            command_type: e.commandType,
+            command_index: e.commandIndex!,
+          },
+        },
+      });
     } else {
       // Preparation failure — command not yet issued
-      ctx.coreVm.notify_error_for_next_command(
-        cause.message,
-        cause.stack,
-        e.commandType
-      );
+      ctx.coreVm.notify_error({
+        message: cause.message,
+        stacktrace: cause.stack,
+        related_command: { Next: { command_type: e.commandType } },
+      });
     }
     return;
   }
@@ -658,29 +699,32 @@ function notifyError(
       ctx.coreVm.sys_end();
     } else if (error instanceof RetryableError) {
       // Retryable with explicit delay
-      ctx.coreVm.notify_error_with_delay_override(
-        error.message,
-        error.stack,
-        error.retryAfter !== undefined
-          ? BigInt(millisOrDurationToMillis(error.retryAfter))
-          : undefined
-      );
+      ctx.coreVm.notify_error({
+        message: error.message,
+        stacktrace: error.stack,
+        delay_override:
+          error.retryAfter !== undefined
+            ? millisOrDurationToMillis(error.retryAfter)
+            : undefined,
+      });
+    } else if (error instanceof PauseError) {
+      // Pause, the invocation is retried only once resumed by an operator
+      ctx.coreVm.notify_pause(error.message, error.stack);
     } else {
-      if (error instanceof PauseError) {
-        ctx.vmLogger.warn(
-          "Throwing PauseError from the handler context will be ignored, " +
-            "as it leads to pause loops. You should use PauseError only inside `ctx.run`"
-        );
-      }
-
       // Transient error — VM decides retry policy
-      ctx.coreVm.notify_error(error.message, error.stack);
+      ctx.coreVm.notify_error({
+        message: error.message,
+        stacktrace: error.stack,
+      });
     }
   } catch (vmError) {
     // Safety net: if sys_write_output_failure or other VM calls fail,
     // fall back to notify_error.
     const inner = ensureError(vmError);
-    ctx.coreVm.notify_error(inner.message, inner.stack);
+    ctx.coreVm.notify_error({
+      message: inner.message,
+      stacktrace: inner.stack,
+    });
   }
 }
 
@@ -859,3 +903,55 @@ function onJournalMismatchErrorsToWasm(
       return vm.WasmJournalMismatchBehavior.Retry;
   }
 }
+
+function logFormatToWasm(
+  format: "text" | "json" | undefined
+): vm.LogFormat | undefined {
+  switch (format) {
+    case "text":
+      return "Text";
+    case "json":
+      return "Json";
+    case undefined:
+      return undefined;
+  }
+}
+
+function awaitingOnPolicyToWasm(
+  policy: "sendAlways" | "dontSendWhenExecutingRun" | "dontSend" | undefined
+): vm.WasmAwaitingOnPolicy | undefined {
+  switch (policy) {
+    case "sendAlways":
+      return "SendAlways";
+    case "dontSendWhenExecutingRun":
+      return "DontSendWhenExecutingRun";
+    case "dontSend":
+      return "DontSend";
+    case undefined:
+      return undefined;
+  }
+}
+
+export function coreOptionsToWasm(
+  options: CoreOptions | undefined
+): vm.WasmVMOptions | undefined {
+  if (options === undefined) {
+    return undefined;
+  }
+  return {
+    log_format: logFormatToWasm(options.logFormat),
+    allow_log_level_override: options.allowLogLevelOverride ?? false,
+    log_batch_size: options.logBatchSize,
+    drop_replay_logs: options.dropReplayLogs ?? false,
+    propagate_trace_context: options.propagateTraceContext ?? false,
+    export_spans: options.spanListener !== undefined,
+    implicit_cancellation: options.implicitCancellation && {
+      cancel_children_calls: options.implicitCancellation.cancelChildrenCalls,
+      cancel_children_one_way_calls:
+        options.implicitCancellation.cancelChildrenOneWayCalls,
+    },
+    awaiting_on_policy: awaitingOnPolicyToWasm(options.awaitingOnPolicy),
+    record_trace: options.recordTrace !== undefined,
+    record_trace_max_bytes: options.recordTrace?.maxBytes,
+  };
+}
@@ -1,5 +1,371 @@
 /* tslint:disable */
 /* eslint-disable */
+/**
+ * A cause of a [`crate::WasmFailure`].
+ */
+export interface WasmFailureCause {
+  code: number;
+  message: string;
+  metadata?: WasmFailureMetadata[];
+}
+
+/**
+ * A service protocol message, see [`decode_protocol_messages`] and [`encode_protocol_messages`].
+ */
+export interface WasmProtocolMessage {
+  message_type: number;
+  flags: number;
+  payload: Uint8Array;
+  /**
+   * Name of the message type, e.g. `CallCommand`.
+   */
+  message_type_name?: string;
+  /**
+   * Index of the command within the decoded buffer, set only for command messages.
+   */
+  command_index?: number;
+  /**
+   * Name of the command, set only for command messages with a non-empty name.
+   */
+  name?: string;
+  /**
+   * Payload decoded as a generic protobuf message, keyed by field number.
+   * Bytes which aren\'t valid UTF-8 are shown as hex, and long values are truncated.
+   */
+  payload_preview?: any;
+}
+
+/**
+ * Additional options for [`WasmVM::new`]. Every field can be omitted.
+ */
+export interface WasmVMOptions {
+  /**
+   * Format of the log records of the VM, defaults to `Text`.
+   */
+  log_format?: LogFormat;
+  /**
+   * If true, the log level of the VM can be overridden per invocation using the `x-restate-sdk-log-level` request header.
+   */
+  allow_log_level_override?: boolean;
+  /**
+   * If set, log records are buffered and delivered in batches through `vm_log_batch`.
+   * The buffer is flushed after `take_output` and `do_progress`, or when it contains this many records.
+   */
+  log_batch_size?: number;
+  /**
+   * If true, span open, record and close events are forwarded to `vm_span_event`.
+   */
+  export_spans?: boolean;
+  /**
+   * If true, the W3C `traceparent` and `tracestate` of the invocation input headers
+   * are propagated to the headers of calls and sends, unless set explicitly.
+   *
+   * The headers of calls and sends are recorded in the journal and checked on replay,
+   * so changing this option while invocations are in flight causes journal mismatches when they\'re retried.
+   */
+  propagate_trace_context?: boolean;
+  /**
+   * If true, core log records emitted while replaying the journal are dropped.
+   * Otherwise they\'re delivered, and can be told apart using the `state` of the log context.
+   */
+  drop_replay_logs?: boolean;
+  /**
+   * If true, inputs, syscalls and outputs are recorded, see [`WasmVM::invocation_trace`].
+   */
+  record_trace?: boolean;
+  /**
+   * Maximum size of the recorded events, measured on their JSON representation, defaults to 4 MiB.
+   * Once reached, the recording stops and the trace is marked as truncated.
+   */
+  record_trace_max_bytes?: number;
+  /**
+   * Children cancelled together with the invocation. Ignored when `explicit_cancellation` is true.
+   */
+  implicit_cancellation?: WasmImplicitCancellationOptions;
+  /**
+   * When the VM tells the runtime what the invocation is awaiting on, defaults to `DontSendWhenExecutingRun`.
+   */
+  awaiting_on_policy?: WasmAwaitingOnPolicy;
+}
+
+/**
+ * Classification of a [`WasmFailure`].
+ */
+export type WasmErrorKind =
+  | "Suspended"
+  | "Closed"
+  | "JournalMismatch"
+  | "ProtocolViolation"
+  | "UnsupportedFeature"
+  | "UnsupportedMediaType"
+  | "BadRequest"
+  | "Internal"
+  | "TerminalFailure"
+  | "Other";
+
+/**
+ * Command an error passed to [`WasmVM::notify_error`] is related to.
+ */
+export type WasmCommandRelationship =
+  | "Last"
+  | { Next: { command_type: WasmCommandType; command_name?: string } }
+  | {
+      Specific: {
+        command_type: WasmCommandType;
+        command_index: number;
+        command_name?: string;
+      };
+    };
+
+/**
+ * First divergence between two journals, see [`diff_journals`].
+ */
+export interface WasmJournalDiff {
+  /**
+   * Position of the diverging command, counting only command messages.
+   */
+  position: number;
+  /**
+   * Not set if the expected journal has fewer commands than the actual one.
+   */
+  expected: WasmJournalDiffCommand | undefined;
+  /**
+   * Not set if the actual journal has fewer commands than the expected one.
+   */
+  actual: WasmJournalDiffCommand | undefined;
+  /**
+   * Differences between the payload previews, empty if the command types differ or one of the commands is missing.
+   */
+  payload_diff: WasmPayloadFieldDiff[];
+}
+
+/**
+ * Format of the log records sent to `vm_log`.
+ */
+export type LogFormat = "Text" | "Json";
+
+/**
+ * Invocation context attached to each log record emitted by a [`WasmVM`].
+ *
+ * It\'s read from the VM when the record is delivered, see [`MakeWebConsoleWriter::begin_call`].
+ */
+export interface WasmLogContext {
+  invocation_id: string | undefined;
+  /**
+   * Same as [`WasmVM::last_command_index`].
+   */
+  command_index: number;
+  state: string;
+}
+
+/**
+ * Log record delivered through `vm_log_batch`.
+ */
+export interface WasmLogRecord {
+  /**
+   * One of the [`LogLevel`] values.
+   */
+  level: number;
+  message: Uint8Array;
+  context: WasmLogContext | undefined;
+}
+
+/**
+ * Options for [`WasmVM::notify_error`]. Only `message` is required.
+ */
+export interface WasmNotifyErrorOptions {
+  /**
+   * Defaults to 500.
+   */
+  code?: number;
+  message: string;
+  stacktrace?: string;
+  /**
+   * The service protocol cannot transport metadata, so it\'s only logged together with the error.
+   */
+  metadata?: WasmFailureMetadata[];
+  /**
+   * Delay in milliseconds before the next retry, overriding the retry policy of the runtime.
+   */
+  delay_override?: number;
+  /**
+   * If true, the runtime pauses the invocation instead of retrying it. Requires service protocol V7 or newer,
+   * otherwise the error is dropped and the VM fails with an unsupported feature error.
+   */
+  should_pause?: boolean;
+  related_command?: WasmCommandRelationship;
+}
+
+/**
+ * Options for the syscalls with a payload, e.g. [`WasmVM::sys_set_state`].
+ */
+export interface WasmPayloadOptions {
+  /**
+   * If true, the payload is serialized non-deterministically,
+   * and it\'s not compared with the journal during replay.
+   */
+  unstable_serialization?: boolean;
+}
+
+/**
+ * Report delivered to `fatal` when the shared core panics.
+ */
+export interface WasmPanicReport {
+  message: string;
+  /**
+   * Source location of the panic, formatted as `file:line:column`.
+   */
+  location: string | undefined;
+  /**
+   * Logger id of the [`crate::WasmVM`] that was running when the panic happened.
+   */
+  logger_id: number | undefined;
+  invocation_id: string | undefined;
+  /**
+   * Same as [`crate::WasmVM::last_command_index`].
+   */
+  command_index: number | undefined;
+  /**
+   * True if the panic is caught, poisoning only the VM it originated from.
+   * This requires the `panic-isolation` feature.
+   */
+  recoverable: boolean;
+}
+
+/**
+ * Result of [`replay_invocation_trace`].
+ */
+export interface WasmTraceReplayResult {
+  /**
+   * Number of trace events replayed, including the mismatching one.
+   */
+  replayed_events: number;
+  /**
+   * First output which differs from the recorded one, if any.
+   */
+  mismatch: WasmTraceOutputMismatch | undefined;
+}
+
+/**
+ * Result of a run, see [`WasmVM::propose_run_completion`].
+ */
+export type WasmRunResult =
+  | { Success: Uint8Array }
+  | { TerminalFailure: WasmFailure }
+  | {
+      RetryableFailure: {
+        error_message: string;
+        error_stacktrace?: string;
+        attempt_duration: number;
+        should_pause?: boolean;
+      };
+    };
+
+/**
+ * Retry policy of a run, see [`WasmVM::propose_run_completion`].
+ */
+export type WasmRetryPolicy =
+  | "Infinite"
+  | "None"
+  | {
+      FixedDelay: {
+        interval?: number;
+        max_attempts?: number;
+        max_duration?: number;
+        on_max_attempts?: WasmOnMaxAttempts;
+      };
+    }
+  | { Exponential: WasmExponentialRetryConfig };
+
+/**
+ * Runtime statistics of a [`WasmVM`], see [`WasmVM::stats`].
+ */
+export interface WasmVMStats {
+  /**
+   * Bytes received through `notify_input`.
+   */
+  input_bytes: number;
+  /**
+   * Bytes emitted through `take_output`.
+   */
+  output_bytes: number;
+  /**
+   * Commands replayed from the journal.
+   */
+  replayed_commands: number;
+  /**
+   * Commands newly added to the journal.
+   */
+  executed_commands: number;
+  /**
+   * Notifications taken through `take_notification`.
+   */
+  taken_notifications: number;
+  /**
+   * Runs the SDK was asked to execute by `do_progress`.
+   */
+  executed_runs: number;
+  /**
+   * Total time spent in `do_progress`, in milliseconds.
+   */
+  do_progress_millis: number;
+}
+
+/**
+ * See [`WasmVMOptions::awaiting_on_policy`]. Requires service protocol V7 or newer, otherwise nothing is sent.
+ */
+export type WasmAwaitingOnPolicy =
+  | "SendAlways"
+  | "DontSendWhenExecutingRun"
+  | "DontSend";
+
+/**
+ * See [`WasmVMOptions::implicit_cancellation`].
+ */
+export interface WasmImplicitCancellationOptions {
+  /**
+   * Cancel the calls, defaults to true.
+   */
+  cancel_children_calls?: boolean;
+  /**
+   * Cancel the one way calls, defaults to false.
+   */
+  cancel_children_one_way_calls?: boolean;
+}
+
+/**
+ * Span lifecycle event forwarded to `vm_span_event`.
+ *
+ * Span ids are unique within a single [`crate::WasmVM`], and are encoded as strings
+ * because they don\'t fit the JavaScript number precision.
+ * Timestamps are milliseconds since the unix epoch.
+ */
+export type WasmSpanEvent =
+  | {
+      Open: {
+        id: string;
+        parent_id: string | undefined;
+        name: string;
+        target: string;
+        level: number;
+        timestamp: number;
+        fields: Record<string, unknown>;
+      };
+    }
+  | {
+      Record: {
+        id: string;
+        timestamp: number;
+        fields: Record<string, unknown>;
+      };
+    }
+  | { Close: { id: string; timestamp: number } };
+
+/**
+ * What to do once the retry policy runs out of attempts or duration.
+ */
+export type WasmOnMaxAttempts = "FailAsTerminal" | "Pause";
+
 export interface WasmAwakeable {
   id: string;
   handle: number;
@@ -16,12 +382,29 @@ export interface WasmExponentialRetryConfig {
   max_interval: number | undefined;
   max_attempts: number | undefined;
   max_duration: number | undefined;
+  /**
+   * What to do once `max_attempts` or `max_duration` is reached, defaults to failing the run.
+   */
+  on_max_attempts?: WasmOnMaxAttempts;
 }
 
 export interface WasmFailure {
   code: number;
   message: string;
   metadata: WasmFailureMetadata[];
+  /**
+   * Chain of causes, starting from the direct cause up to the root cause.
+   * Causes are recorded in the journal, and survive the round trip through other services.
+   */
+  causes?: WasmFailureCause[];
+  /**
+   * Set for failures returned by the VM, see `is_suspended` and `is_retryable`.
+   */
+  kind?: WasmErrorKind;
+  /**
+   * Set only when converting errors of the shared core, ignored when the failure is recorded in the journal.
+   */
+  stacktrace?: string;
 }
 
 export interface WasmFailureMetadata {
@@ -29,6 +412,25 @@ export interface WasmFailureMetadata {
   value: string;
 }
 
+export interface WasmJournalDiffCommand {
+  message_type: number;
+  message_type_name: string;
+  /**
+   * As decoded by `decode_protocol_messages`, if set.
+   */
+  command_index: number | undefined;
+  name: string | undefined;
+}
+
+export interface WasmPayloadFieldDiff {
+  /**
+   * Path of the field in the payload preview, e.g. `3.1` for field 1 of the message in field 3.
+   */
+  path: string;
+  expected: any;
+  actual: any;
+}
+
 export interface WasmRun {
   replayed: boolean;
   handle: number;
@@ -38,6 +440,18 @@ export interface WasmSendHandle {
   invocation_id_completion_id: number;
 }
 
+export interface WasmTraceOutputMismatch {
+  /**
+   * Index of the `TakeOutput` event within the trace.
+   */
+  event_index: number;
+  /**
+   * Can be inspected with `decode_protocol_messages`.
+   */
+  expected: Uint8Array;
+  actual: Uint8Array;
+}
+
 export type WasmAsyncResultValue =
   | "NotReady"
   | "Empty"
@@ -150,6 +564,12 @@ export class WasmVM {
   [Symbol.dispose](): void;
   do_progress(future: WasmUnresolvedFuture): WasmDoProgressResult;
   get_response_head(): WasmResponseHead;
+  /**
+   * Returns the trace recorded so far as a JSON string, or `None` if [`WasmVMOptions::record_trace`] is disabled.
+   *
+   * The trace can be replayed with `replay_invocation_trace`.
+   */
+  invocation_trace(): string | undefined;
   is_completed(handle: number): boolean;
   is_processing(): boolean;
   is_ready_to_execute(): boolean;
@@ -160,52 +580,31 @@ export class WasmVM {
     logger_id: number,
     disable_payload_checks: boolean,
     explicit_cancellation: boolean,
-    on_journal_mismatch: WasmJournalMismatchBehavior
+    on_journal_mismatch: WasmJournalMismatchBehavior,
+    options?: WasmVMOptions | null
   );
-  notify_error(error_message: string, stacktrace?: string | null): void;
-  notify_error_for_next_command(
-    error_message: string,
-    stacktrace: string | null | undefined,
-    wasm_command_type: WasmCommandType
-  ): void;
-  notify_error_for_specific_command(
-    error_message: string,
-    stacktrace: string | null | undefined,
-    wasm_command_type: WasmCommandType,
-    command_index: number,
-    command_name?: string | null
-  ): void;
-  notify_error_with_delay_override(
-    error_message: string,
-    stacktrace?: string | null,
-    delay_override?: bigint | null
-  ): void;
+  notify_error(options: WasmNotifyErrorOptions): void;
   notify_input(buffer: Uint8Array): void;
   notify_input_closed(): void;
-  propose_run_completion_failure(handle: number, value: WasmFailure): void;
-  propose_run_completion_failure_transient(
-    handle: number,
-    error_message: string,
-    error_stacktrace: string | null | undefined,
-    attempt_duration: bigint,
-    config?: WasmExponentialRetryConfig | null
-  ): void;
-  propose_run_completion_failure_transient_with_delay_override(
-    handle: number,
-    error_message: string,
-    error_stacktrace: string | null | undefined,
-    attempt_duration: bigint,
-    delay_override?: bigint | null,
-    max_retry_attempts_override?: number | null,
-    max_retry_duration_override?: bigint | null
-  ): void;
-  propose_run_completion_failure_transient_with_pause(
+  /**
+   * Pauses the invocation after an error, instead of retrying it, so it can be resumed by an operator.
+   * Requires service protocol V7 or newer.
+   */
+  notify_pause(reason: string, stacktrace?: string | null): void;
+  /**
+   * Completes the run with the given result. Retryable failures are retried following `retry_policy`,
+   * defaulting to [`WasmRetryPolicy::Infinite`]. The retry policy is ignored for other results.
+   */
+  propose_run_completion(
     handle: number,
-    error_message: string,
-    error_stacktrace: string | null | undefined,
-    attempt_duration: bigint
+    result: WasmRunResult,
+    retry_policy?: WasmRetryPolicy | null
   ): void;
-  propose_run_completion_success(handle: number, buffer: Uint8Array): void;
+  /**
+   * Changes the log level of this VM, without affecting the overall log subscriber.
+   */
+  set_log_level(level: LogLevel): void;
+  stats(): WasmVMStats;
   sys_attach_invocation(invocation_id: string): number;
   sys_awakeable(): WasmAwakeable;
   sys_call(
@@ -217,15 +616,32 @@ export class WasmVM {
     idempotency_key?: string | null,
     scope?: string | null,
     limit_key?: string | null,
-    name?: string | null
+    name?: string | null,
+    options?: WasmPayloadOptions | null
   ): WasmCallHandle;
   sys_cancel_invocation(target_invocation_id: string): void;
   sys_clear_all_state(): void;
   sys_clear_state(key: string): void;
-  sys_complete_awakeable_failure(id: string, value: WasmFailure): void;
-  sys_complete_awakeable_success(id: string, buffer: Uint8Array): void;
-  sys_complete_promise_failure(key: string, value: WasmFailure): number;
-  sys_complete_promise_success(key: string, buffer: Uint8Array): number;
+  sys_complete_awakeable_failure(
+    id: string,
+    value: WasmFailure,
+    options?: WasmPayloadOptions | null
+  ): void;
+  sys_complete_awakeable_success(
+    id: string,
+    buffer: Uint8Array,
+    options?: WasmPayloadOptions | null
+  ): void;
+  sys_complete_promise_failure(
+    key: string,
+    value: WasmFailure,
+    options?: WasmPayloadOptions | null
+  ): number;
+  sys_complete_promise_success(
+    key: string,
+    buffer: Uint8Array,
+    options?: WasmPayloadOptions | null
+  ): number;
   sys_complete_signal_failure(
     invocation_id: string,
     signal_name: string,
@@ -239,7 +655,7 @@ export class WasmVM {
   sys_end(): void;
   sys_get_invocation_output(invocation_id: string): number;
   sys_get_promise(key: string): number;
-  sys_get_state(key: string): number;
+  sys_get_state(key: string, options?: WasmPayloadOptions | null): number;
   sys_get_state_keys(): number;
   sys_input(): WasmInput;
   sys_peek_promise(key: string): number;
@@ -254,13 +670,24 @@ export class WasmVM {
     idempotency_key?: string | null,
     scope?: string | null,
     limit_key?: string | null,
-    name?: string | null
+    name?: string | null,
+    options?: WasmPayloadOptions | null
   ): WasmSendHandle;
-  sys_set_state(key: string, buffer: Uint8Array): void;
+  sys_set_state(
+    key: string,
+    buffer: Uint8Array,
+    options?: WasmPayloadOptions | null
+  ): void;
   sys_signal(signal_name: string): number;
   sys_sleep(millis: bigint, name?: string | null): number;
-  sys_write_output_failure(value: WasmFailure): void;
-  sys_write_output_success(buffer: Uint8Array): void;
+  sys_write_output_failure(
+    value: WasmFailure,
+    options?: WasmPayloadOptions | null
+  ): void;
+  sys_write_output_success(
+    buffer: Uint8Array,
+    options?: WasmPayloadOptions | null
+  ): void;
   take_notification(handle: number): WasmAsyncResultValue;
   take_output(): Uint8Array;
 }
@@ -268,9 +695,64 @@ export class WasmVM {
 export function cancel_handle(): number;
 
 /**
- * This will set the log level of the overall log subscriber.
+ * Decodes a buffer of service protocol frames, as passed to `WasmVM.notify_input` or returned by `WasmVM.take_output`.
+ *
+ * Commands are numbered starting from `first_command_index`, defaulting to 0.
+ */
+export function decode_protocol_messages(
+  buffer: Uint8Array,
+  first_command_index?: number | null
+): WasmProtocolMessage[];
+
+/**
+ * Compares the commands of two journals, returning the first diverging command, or `None` if they match.
+ *
+ * Journals are lists of protocol messages, as returned by `decode_protocol_messages`,
+ * for example decoded from the inputs and outputs of a trace recorded with `WasmVMOptions.record_trace`.
+ * Non-command messages are ignored.
+ */
+export function diff_journals(
+  expected: WasmProtocolMessage[],
+  actual: WasmProtocolMessage[]
+): WasmJournalDiff | undefined;
+
+/**
+ * Encodes the given messages as service protocol frames, the inverse of [`decode_protocol_messages`].
+ */
+export function encode_protocol_messages(
+  messages: WasmProtocolMessage[]
+): Uint8Array;
+
+/**
+ * Returns true if the runtime will retry the invocation after the failure.
+ *
+ * Suspensions, closed input streams and terminal failures are not retried.
+ */
+export function is_retryable(failure: WasmFailure): boolean;
+
+/**
+ * Returns true if the failure is caused by the invocation being suspended.
+ * In this case the handler should stop executing, without reporting the failure.
+ */
+export function is_suspended(failure: WasmFailure): boolean;
+
+/**
+ * Replays a trace recorded with [`WasmVMOptions::record_trace`] on a fresh VM, checking its outputs match the recorded ones.
+ *
+ * Replay stops at the first output mismatch. Truncated traces are replayed up to the last recorded event.
+ */
+export function replay_invocation_trace(
+  trace: string,
+  log_level: LogLevel,
+  logger_id: number
+): WasmTraceReplayResult;
+
+/**
+ * This will set the log level and format of the overall log subscriber.
+ *
+ * Can be invoked several times, the latest invocation wins.
  */
-export function set_log_level(level: LogLevel): void;
+export function set_log_level(level: LogLevel, format?: LogFormat | null): void;
 
 /**
  * Setups the WASM module
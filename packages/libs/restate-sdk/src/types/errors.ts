@@ -40,7 +40,11 @@ export function ensureError(
   }
   if (typeof e === "object" && e !== null && "code" in e && "message" in e) {
     // This is an error from the VM
-    return new RestateError(e.message as string, {
+    const stacktrace = (e as { stacktrace?: string }).stacktrace;
+    const message = stacktrace
+      ? `${e.message as string}\nStacktrace: ${stacktrace}`
+      : (e.message as string);
+    return new RestateError(message, {
       errorCode: e.code as number,
     });
   }
@@ -184,8 +188,8 @@ export class RetryableError extends RestateError {
 }
 
 /**
- * When thrown, Restate will pause the execution of the current invocation.
- * This error type MUST be used only within a `ctx.run` closure. If thrown outside a `ctx.run` closure, pause will be ignored.
+ * When thrown, Restate will pause the execution of the current invocation, until it's resumed by an operator.
+ * This error type can be thrown within a `ctx.run` closure, or from the handler.
  *
  * You can wrap another error using {@link from}.
  */
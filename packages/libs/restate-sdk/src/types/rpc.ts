@@ -592,6 +592,13 @@ export type ServiceHandlerOpts<I, O> = {
    * @experimental
    */
   onJournalMismatchErrors?: "retry" | "pause" | "fail";
+
+  /**
+   * Advanced options of the SDK core state machine, see {@link CoreOptions}.
+   *
+   * @experimental
+   */
+  coreOptions?: CoreOptions;
 };
 
 export type ObjectHandlerOpts<I, O> = ServiceHandlerOpts<I, O> & {
@@ -986,6 +993,119 @@ export type RetryPolicy = {
   exponentiationFactor?: number;
 };
 
+/**
+ * Advanced options of the SDK core state machine driving every invocation.
+ *
+ * @experimental
+ */
+export type CoreOptions = {
+  /**
+   * Format of the log records emitted by the SDK core. Defaults to `"text"`.
+   *
+   * With `"json"`, every record is a JSON object containing level, target, span stack and event fields.
+   */
+  logFormat?: "text" | "json";
+
+  /**
+   * When set to `true`, the log level of the SDK core can be overridden per invocation
+   * using the `x-restate-sdk-log-level` request header.
+   */
+  allowLogLevelOverride?: boolean;
+
+  /**
+   * When set, the log records of the SDK core are buffered and delivered to the logger in batches
+   * of at most this many records.
+   */
+  logBatchSize?: number;
+
+  /**
+   * When set to `true`, the log records emitted by the SDK core while replaying the journal are dropped.
+   */
+  dropReplayLogs?: boolean;
+
+  /**
+   * When set to `true`, the W3C `traceparent` and `tracestate` headers of the invocation
+   * are propagated to the headers of calls and sends, unless set explicitly.
+   *
+   * *NOTE:* the headers of calls and sends are recorded in the journal and checked on replay,
+   * so changing this option while invocations are in flight makes them fail with journal mismatches when retried.
+   */
+  propagateTraceContext?: boolean;
+
+  /**
+   * When set, the open, record and close events of the spans of the SDK core are delivered to this listener,
+   * for example to export them to a tracing backend.
+   */
+  spanListener?: (event: CoreSpanEvent) => void;
+
+  /**
+   * Which children are cancelled together with the invocation.
+   * By default calls are cancelled, while one way calls are not.
+   *
+   * Ignored when `explicitCancellation` is set to `true`.
+   */
+  implicitCancellation?: {
+    cancelChildrenCalls?: boolean;
+    cancelChildrenOneWayCalls?: boolean;
+  };
+
+  /**
+   * When the SDK core tells the runtime what the invocation is awaiting on. Defaults to `"dontSendWhenExecutingRun"`.
+   *
+   * * `"sendAlways"`: every time the invocation waits for external progress.
+   * * `"dontSendWhenExecutingRun"`: like `"sendAlways"`, except while a `ctx.run` is executing.
+   * * `"dontSend"`: never.
+   *
+   * *NOTE:* requires the service protocol V7 or newer, otherwise nothing is sent.
+   */
+  awaitingOnPolicy?: "sendAlways" | "dontSendWhenExecutingRun" | "dontSend";
+
+  /**
+   * When set, the inputs, syscalls and outputs of the SDK core are recorded, and the trace is delivered
+   * as a JSON string to `listener` at the end of every invocation attempt.
+   * Traces can be attached to bug reports, to reproduce the attempt without a Restate server.
+   *
+   * The values of the request headers carrying credentials are redacted, while payloads are recorded as they are.
+   */
+  recordTrace?: {
+    listener: (trace: string) => void;
+    /**
+     * Maximum size of the recorded events, in bytes. Defaults to 4 MiB.
+     * Once reached, the recording stops and the trace is marked as `truncated`.
+     */
+    maxBytes?: number;
+  };
+};
+
+/**
+ * Span lifecycle event of the SDK core, see {@link CoreOptions.spanListener}.
+ *
+ * Span ids are unique within a single invocation attempt.
+ * Timestamps are milliseconds since the unix epoch.
+ *
+ * @experimental
+ */
+export type CoreSpanEvent =
+  | {
+      Open: {
+        id: string;
+        parent_id?: string;
+        name: string;
+        target: string;
+        level: number;
+        timestamp: number;
+        fields: Record<string, unknown>;
+      };
+    }
+  | {
+      Record: {
+        id: string;
+        timestamp: number;
+        fields: Record<string, unknown>;
+      };
+    }
+  | { Close: { id: string; timestamp: number } };
+
 export type ServiceOptions = {
   /**
    * The retention duration of idempotent requests to this service.
@@ -1164,6 +1284,13 @@ export type ServiceOptions = {
    * @experimental
    */
   onJournalMismatchErrors?: "retry" | "pause" | "fail";
+
+  /**
+   * Advanced options of the SDK core state machine, see {@link CoreOptions}.
+   *
+   * @experimental
+   */
+  coreOptions?: CoreOptions;
 };
 
 /**
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2023-2024 - Restate Software, Inc., Restate GmbH
+ *
+ * This file is part of the Restate SDK for Node.js/TypeScript,
+ * which is released under the MIT license.
+ *
+ * You can find a copy of the license in file LICENSE in the root
+ * directory of this repository or package, or at
+ * https://github.com/restatedev/sdk-typescript/blob/main/LICENSE
+ */
+
+import * as restate from "../src/index.js";
+import { EndpointBuilder } from "../src/endpoint/endpoint.js";
+import { coreOptionsToWasm } from "../src/endpoint/handlers/generic.js";
+import { describe, expect, it } from "vitest";
+
+/* eslint-disable @typescript-eslint/require-await */
+const awaitingOn = restate.service({
+  name: "awaitingOn",
+  handlers: {
+    serviceDefault: async (_ctx: restate.Context) => {},
+    sendAlways: restate.handlers.handler(
+      { coreOptions: { awaitingOnPolicy: "sendAlways" } },
+      async (_ctx: restate.Context) => {}
+    ),
+    logFormatOnly: restate.handlers.handler(
+      { coreOptions: { logFormat: "json" } },
+      async (_ctx: restate.Context) => {}
+    ),
+  },
+  options: {
+    coreOptions: { awaitingOnPolicy: "dontSend" },
+  },
+});
+
+const noCoreOptions = restate.service({
+  name: "noCoreOptions",
+  handlers: {
+    greet: async (_ctx: restate.Context) => {},
+  },
+});
+/* eslint-enable @typescript-eslint/require-await */
+
+function wasmOptions(componentName: string, handlerName: string) {
+  const endpointBuilder = new EndpointBuilder();
+  endpointBuilder.bind(awaitingOn);
+  endpointBuilder.bind(noCoreOptions);
+  const handler = endpointBuilder
+    .build()
+    .components.get(componentName)
+    ?.handlerMatching({ type: "invoke", componentName, handlerName });
+  expect(handler).toBeDefined();
+  return coreOptionsToWasm(handler!.executionOptions.coreOptions);
+}
+
+describe("CoreOptions", () => {
+  it("awaitingOnPolicy defaults to the service options", () => {
+    const options = wasmOptions("awaitingOn", "serviceDefault");
+    expect(options?.awaiting_on_policy).toBe("DontSend");
+  });
+
+  it("awaitingOnPolicy can be overridden per handler", () => {
+    const options = wasmOptions("awaitingOn", "sendAlways");
+    expect(options?.awaiting_on_policy).toBe("SendAlways");
+  });
+
+  it("awaitingOnPolicy is kept when the handler sets other core options", () => {
+    const options = wasmOptions("awaitingOn", "logFormatOnly");
+    expect(options?.awaiting_on_policy).toBe("DontSend");
+    expect(options?.log_format).toBe("Json");
+  });
+
+  it("awaitingOnPolicy is left to the core default when not set", () => {
+    expect(wasmOptions("noCoreOptions", "greet")).toBeUndefined();
+  });
+});
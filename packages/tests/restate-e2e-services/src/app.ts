@@ -26,6 +26,7 @@ import "./kill.js";
 import "./virtual_object_command_interpreter.js";
 import "./promise_combinators.js";
 import "./explicit_cancellation.js";
+import "./implicit_cancellation.js";
 import "./signals.js";
 import "./preview_serdes.js";
 import "./ingress_default_serde.js";
@@ -794,6 +794,25 @@ function createHookLevelSuite(level: HookLevel) {
     },
   });
 
+  const runMaxRetryPauseService = createService({
+    name: `${level}_RunMaxRetryPause`,
+    ...hooksAt([recordHookEvents()]),
+    handler: async (ctx, _) => {
+      await ctx.run(
+        "flaky-step",
+        () => {
+          throw new Error("always fails");
+        },
+        {
+          maxRetryAttempts: 2,
+          initialRetryInterval: 10,
+          pauseOnMaxRetries: true,
+        }
+      );
+      return { invocationId: ctx.request().id };
+    },
+  });
+
   class PaymentRejected extends Error {
     constructor() {
       super("Payment rejected");
@@ -1049,6 +1068,7 @@ function createHookLevelSuite(level: HookLevel) {
     runSerdeFailService,
     mapErrorService,
     runMaxRetryService,
+    runMaxRetryPauseService,
     asTerminalErrorService,
     journalMismatchService,
     abortTimeoutService,
@@ -1088,6 +1108,7 @@ function createHookLevelSuite(level: HookLevel) {
       runSerdeFailService,
       mapErrorService,
       runMaxRetryService,
+      runMaxRetryPauseService,
       asTerminalErrorService,
       journalMismatchService,
       abortTimeoutService,
@@ -0,0 +1,60 @@
+// Copyright (c) 2023 - Restate Software, Inc., Restate GmbH
+//
+// This file is part of the Restate e2e tests,
+// which are released under the MIT license.
+//
+// You can find a copy of the license in file LICENSE in the root
+// directory of this repository or package, or at
+// https://github.com/restatedev/e2e/blob/main/LICENSE
+
+import * as restate from "@restatedev/restate-sdk";
+import { REGISTRY } from "./services.js";
+
+// Target of the one way calls, blocks until cancelled
+const blockForeverOneWay = restate.service({
+  name: "BlockForeverOneWay",
+  handlers: {
+    block: async (ctx: restate.Context): Promise<string> => {
+      await ctx.sleep({ days: 1 });
+      return "should-never-return";
+    },
+  },
+});
+
+const BlockForeverOneWay: typeof blockForeverOneWay = {
+  name: "BlockForeverOneWay",
+};
+
+async function sendAndBlock(ctx: restate.Context): Promise<string> {
+  ctx.serviceSendClient(BlockForeverOneWay).block();
+  await ctx.sleep({ days: 1 });
+  return "not-cancelled";
+}
+
+const implicitCancellationService = restate.service({
+  name: "ImplicitCancellation",
+  handlers: {
+    /**
+     * Send a one way call, then block until cancelled.
+     * With the default options the one way call isn't cancelled together with this invocation.
+     */
+    sendAndBlock,
+
+    /**
+     * Same as sendAndBlock, cancelling the one way call together with this invocation.
+     */
+    sendAndBlockCancellingOneWayCalls: restate.handlers.handler(
+      {
+        coreOptions: {
+          implicitCancellation: { cancelChildrenOneWayCalls: true },
+        },
+      },
+      sendAndBlock
+    ),
+  },
+});
+
+REGISTRY.addService(blockForeverOneWay);
+REGISTRY.addService(implicitCancellationService);
+
+export type ImplicitCancellation = typeof implicitCancellationService;
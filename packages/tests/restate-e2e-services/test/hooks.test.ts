@@ -61,6 +61,7 @@ function hooksSuite(level: HookLevel) {
       runSerdeFailService,
       mapErrorService,
       runMaxRetryService,
+      runMaxRetryPauseService,
       asTerminalErrorService,
       journalMismatchService,
       abortTimeoutService,
@@ -719,6 +720,38 @@ function hooksSuite(level: HookLevel) {
       ]);
     });
 
+    it("ctx.run with pauseOnMaxRetries — retries then pauses", async () => {
+      const ingress = clients.connect({ url: getIngressUrl() });
+      const send = await ingress
+        .serviceSendClient(runMaxRetryPauseService)
+        .invoke("");
+
+      await waitForInvocationOutcome(
+        getAdminUrl(),
+        send.invocationId,
+        {
+          status: "paused",
+        },
+        {
+          timeout: 10_000,
+          interval: 100,
+        }
+      );
+
+      expect(await hooksDriver.getEvents(send.invocationId)).toEqual([
+        // attempt 1: run fails — abandoned
+        "hook:handler:before",
+        "hook:run:flaky-step:before",
+        "hook:run:flaky-step:error:[rw] always fails",
+        "hook:handler:error:[hw] (500) [rw] always fails",
+        // attempt 2: run fails — paused (maxRetryAttempts exhausted), no terminal error
+        "hook:handler:before",
+        "hook:run:flaky-step:before",
+        "hook:run:flaky-step:error:[rw] always fails",
+        expect.stringMatching(/^hook:handler:error:\[hw\] \(\d+\) /),
+      ]);
+    });
+
     it("asTerminalError converts domain error to terminal", async () => {
       const client = clients
         .connect({ url: getIngressUrl() })
@@ -0,0 +1,96 @@
+// Copyright (c) 2023 - Restate Software, Inc., Restate GmbH
+//
+// This file is part of the Restate e2e tests,
+// which are released under the MIT license.
+//
+// You can find a copy of the license in file LICENSE in the root
+// directory of this repository or package, or at
+// https://github.com/restatedev/e2e/blob/main/LICENSE
+
+import { randomUUID } from "node:crypto";
+import { describe, it, expect } from "vitest";
+import { rpc } from "@restatedev/restate-sdk-clients";
+import { getAdminUrl, ingressClient } from "./utils.js";
+import { waitForInvocationOutcome } from "./hooks_utils.js";
+import type { ImplicitCancellation } from "../src/implicit_cancellation.js";
+
+const ImplicitCancellation: ImplicitCancellation = {
+  name: "ImplicitCancellation",
+};
+
+function idempotentSend<I = unknown>() {
+  return rpc.sendOpts<I>({ idempotencyKey: randomUUID() });
+}
+
+async function cancelInvocation(invocationId: string): Promise<void> {
+  const res = await fetch(
+    `${getAdminUrl()}/invocations/${invocationId}/cancel`,
+    { method: "PATCH" }
+  );
+  if (res.ok) return;
+  throw new Error(
+    `Failed to cancel invocation ${invocationId}: ${res.status} ${await res.text()}`
+  );
+}
+
+async function childInvocationIds(invocationId: string): Promise<string[]> {
+  const res = await fetch(`${getAdminUrl()}/query`, {
+    method: "POST",
+    headers: { "Content-Type": "application/json", Accept: "application/json" },
+    body: JSON.stringify({
+      query: `SELECT id FROM sys_invocation WHERE invoked_by_id = '${invocationId}'`,
+    }),
+  });
+  const json = (await res.json()) as { rows: { id: string }[] };
+  return json.rows.map((r) => r.id);
+}
+
+async function waitForChild(invocationId: string): Promise<string> {
+  await expect
+    .poll(() => childInvocationIds(invocationId), {
+      timeout: 10_000,
+      interval: 100,
+    })
+    .toHaveLength(1);
+  return (await childInvocationIds(invocationId))[0]!;
+}
+
+describe("ImplicitCancellation", () => {
+  const ingress = ingressClient();
+
+  it("sendAndBlock: one way calls are not cancelled by default", async () => {
+    const send = await ingress
+      .serviceSendClient(ImplicitCancellation)
+      .sendAndBlock(idempotentSend());
+    const childId = await waitForChild(send.invocationId);
+
+    await cancelInvocation(send.invocationId);
+
+    await expect(ingress.result(send)).rejects.toThrow("Cancelled");
+    await expect(
+      waitForInvocationOutcome(
+        getAdminUrl(),
+        childId,
+        { status: "completed" },
+        { timeout: 2_000 }
+      )
+    ).rejects.toThrow();
+
+    await cancelInvocation(childId);
+  }, 30_000);
+
+  it("sendAndBlockCancellingOneWayCalls: one way calls are cancelled together with the invocation", async () => {
+    const send = await ingress
+      .serviceSendClient(ImplicitCancellation)
+      .sendAndBlockCancellingOneWayCalls(idempotentSend());
+    const childId = await waitForChild(send.invocationId);
+
+    await cancelInvocation(send.invocationId);
+
+    await expect(ingress.result(send)).rejects.toThrow("Cancelled");
+    await waitForInvocationOutcome(getAdminUrl(), childId, {
+      status: "completed",
+      journalOutput: { failure: { code: 409 } },
+    });
+  }, 30_000);
+});
@@ -0,0 +1,93 @@
+use crate::WasmFailure;
+use restate_sdk_shared_core::error::codes;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+/// Classification of a [`WasmFailure`].
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmErrorKind {
+    /// The invocation is suspended, waiting for the runtime to resume it.
+    Suspended,
+    /// The input stream was closed, and the state machine can't make any further progress.
+    Closed,
+    /// Non-determinism error, see the `journal_mismatch.*` metadata.
+    JournalMismatch,
+    /// The runtime and the SDK disagree on the service protocol.
+    ProtocolViolation,
+    /// The feature is not supported by the negotiated service protocol version.
+    UnsupportedFeature,
+    UnsupportedMediaType,
+    BadRequest,
+    Internal,
+    /// Terminal failure, either recorded by the user code or received as the result of another operation.
+    TerminalFailure,
+    /// Any other error code.
+    Other,
+}
+
+impl WasmErrorKind {
+    pub(crate) fn from_code(code: u16) -> Self {
+        match code.into() {
+            codes::SUSPENDED => WasmErrorKind::Suspended,
+            codes::CLOSED => WasmErrorKind::Closed,
+            codes::JOURNAL_MISMATCH => WasmErrorKind::JournalMismatch,
+            codes::PROTOCOL_VIOLATION | codes::AWAITING_TWO_ASYNC_RESULTS => {
+                WasmErrorKind::ProtocolViolation
+            }
+            codes::UNSUPPORTED_FEATURE => WasmErrorKind::UnsupportedFeature,
+            codes::UNSUPPORTED_MEDIA_TYPE => WasmErrorKind::UnsupportedMediaType,
+            codes::BAD_REQUEST => WasmErrorKind::BadRequest,
+            codes::INTERNAL => WasmErrorKind::Internal,
+            _ => WasmErrorKind::Other,
+        }
+    }
+}
+
+/// Returns true if the failure is caused by the invocation being suspended.
+/// In this case the handler should stop executing, without reporting the failure.
+#[wasm_bindgen]
+pub fn is_suspended(failure: WasmFailure) -> bool {
+    failure.kind == Some(WasmErrorKind::Suspended)
+}
+
+/// Returns true if the runtime will retry the invocation after the failure.
+///
+/// Suspensions, closed input streams and terminal failures are not retried.
+#[wasm_bindgen]
+pub fn is_retryable(failure: WasmFailure) -> bool {
+    !matches!(
+        failure.kind,
+        Some(WasmErrorKind::Suspended | WasmErrorKind::Closed | WasmErrorKind::TerminalFailure)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restate_sdk_shared_core::Error;
+
+    #[test]
+    fn failure_from_error() {
+        let failure = WasmFailure::from(
+            Error::new(codes::INTERNAL, "Something went wrong")
+                .with_stacktrace("at foo()\nat bar()"),
+        );
+
+        assert_eq!(failure.code, 500);
+        assert_eq!(failure.message, "Something went wrong");
+        assert_eq!(failure.stacktrace.as_deref(), Some("at foo()\nat bar()"));
+        assert!(failure.kind == Some(WasmErrorKind::Internal));
+        assert!(is_retryable(failure));
+    }
+
+    #[test]
+    fn failure_from_error_without_stacktrace() {
+        let failure = WasmFailure::from(Error::new(codes::SUSPENDED, "Suspended"));
+
+        assert!(failure.stacktrace.is_none());
+        assert!(is_suspended(failure.clone()));
+        assert!(!is_retryable(failure));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::WasmFailureMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tsify::Tsify;
+
+// The service protocol failures have no causes, so they're stored in the failure metadata as
+// `restate.cause.<index>.code`, `restate.cause.<index>.message` and `restate.cause.<index>.metadata.<key>`.
+const CAUSE_PREFIX: &str = "restate.cause.";
+const CODE_SUFFIX: &str = "code";
+const MESSAGE_SUFFIX: &str = "message";
+const METADATA_PREFIX: &str = "metadata.";
+
+/// A cause of a [`crate::WasmFailure`].
+#[derive(Tsify, Serialize, Deserialize, Clone, Default)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmFailureCause {
+    pub code: u16,
+    pub message: String,
+    #[tsify(optional)]
+    #[serde(default)]
+    pub metadata: Vec<WasmFailureMetadata>,
+}
+
+/// Appends the causes to the failure metadata, the inverse of [`decode_causes`].
+pub(crate) fn encode_causes(metadata: &mut Vec<(String, String)>, causes: Vec<WasmFailureCause>) {
+    for (index, cause) in causes.into_iter().enumerate() {
+        let prefix = format!("{CAUSE_PREFIX}{index}.");
+        metadata.push((format!("{prefix}{CODE_SUFFIX}"), cause.code.to_string()));
+        metadata.push((format!("{prefix}{MESSAGE_SUFFIX}"), cause.message));
+        metadata.extend(
+            cause
+                .metadata
+                .into_iter()
+                .map(|m| (format!("{prefix}{METADATA_PREFIX}{}", m.key), m.value)),
+        );
+    }
+}
+
+/// Splits the failure metadata into the user metadata and the causes, ordered by index.
+/// Entries which cannot be parsed are left in the user metadata.
+pub(crate) fn decode_causes(
+    metadata: Vec<(String, String)>,
+) -> (Vec<WasmFailureMetadata>, Vec<WasmFailureCause>) {
+    let mut user_metadata = vec![];
+    let mut causes = BTreeMap::<usize, WasmFailureCause>::new();
+
+    for (key, value) in metadata {
+        let Some((index, field)) = key
+            .strip_prefix(CAUSE_PREFIX)
+            .and_then(|rest| rest.split_once('.'))
+            .and_then(|(index, field)| Some((index.parse::<usize>().ok()?, field)))
+        else {
+            user_metadata.push(WasmFailureMetadata { key, value });
+            continue;
+        };
+
+        if field == CODE_SUFFIX {
+            if let Ok(code) = value.parse() {
+                causes.entry(index).or_default().code = code;
+                continue;
+            }
+        } else if field == MESSAGE_SUFFIX {
+            causes.entry(index).or_default().message = value;
+            continue;
+        } else if let Some(cause_key) = field.strip_prefix(METADATA_PREFIX) {
+            causes
+                .entry(index)
+                .or_default()
+                .metadata
+                .push(WasmFailureMetadata {
+                    key: cause_key.to_owned(),
+                    value,
+                });
+            continue;
+        }
+        user_metadata.push(WasmFailureMetadata { key, value });
+    }
+
+    (user_metadata, causes.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(entries: &[(&str, &str)]) -> Vec<WasmFailureMetadata> {
+        entries
+            .iter()
+            .map(|(key, value)| WasmFailureMetadata {
+                key: (*key).to_owned(),
+                value: (*value).to_owned(),
+            })
+            .collect()
+    }
+
+    fn pairs(metadata: &[WasmFailureMetadata]) -> Vec<(&str, &str)> {
+        metadata
+            .iter()
+            .map(|m| (m.key.as_str(), m.value.as_str()))
+            .collect()
+    }
+
+    fn owned(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut encoded = owned(&[("user.key", "user value")]);
+        encode_causes(
+            &mut encoded,
+            vec![
+                WasmFailureCause {
+                    code: 500,
+                    message: "first".to_owned(),
+                    metadata: metadata(&[("a", "1"), ("b.c", "2")]),
+                },
+                WasmFailureCause {
+                    code: 409,
+                    message: "second".to_owned(),
+                    metadata: vec![],
+                },
+            ],
+        );
+
+        let (user_metadata, causes) = decode_causes(encoded);
+
+        assert_eq!(pairs(&user_metadata), [("user.key", "user value")]);
+        assert_eq!(causes.len(), 2);
+        assert_eq!(causes[0].code, 500);
+        assert_eq!(causes[0].message, "first");
+        assert_eq!(pairs(&causes[0].metadata), [("a", "1"), ("b.c", "2")]);
+        assert_eq!(causes[1].code, 409);
+        assert_eq!(causes[1].message, "second");
+        assert!(causes[1].metadata.is_empty());
+    }
+
+    #[test]
+    fn encode_without_causes() {
+        let mut encoded = owned(&[("key", "value")]);
+        encode_causes(&mut encoded, vec![]);
+
+        assert_eq!(encoded, owned(&[("key", "value")]));
+    }
+
+    #[test]
+    fn decode_orders_causes_by_index() {
+        let (user_metadata, causes) = decode_causes(owned(&[
+            ("restate.cause.10.message", "ten"),
+            ("restate.cause.2.message", "two"),
+            ("restate.cause.2.code", "400"),
+        ]));
+
+        assert!(user_metadata.is_empty());
+        assert_eq!(causes.len(), 2);
+        assert_eq!((causes[0].code, causes[0].message.as_str()), (400, "two"));
+        assert_eq!((causes[1].code, causes[1].message.as_str()), (0, "ten"));
+    }
+
+    #[test]
+    fn decode_keeps_unparsable_entries_in_user_metadata() {
+        let entries = [
+            ("other.key", "value"),
+            ("restate.other", "value"),
+            ("restate.cause.", "value"),
+            ("restate.cause.0", "value"),
+            ("restate.cause.x.message", "value"),
+            ("restate.cause.-1.message", "value"),
+            ("restate.cause.0.code", "not a code"),
+            ("restate.cause.0.code", "70000"),
+            ("restate.cause.0.unknown", "value"),
+        ];
+
+        let (user_metadata, causes) = decode_causes(owned(&entries));
+
+        assert_eq!(pairs(&user_metadata), entries);
+        assert!(causes.is_empty());
+    }
+}
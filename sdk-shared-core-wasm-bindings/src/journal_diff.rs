@@ -0,0 +1,285 @@
+use crate::protocol::{inspect_payload, is_command, message_type_name, WasmProtocolMessage};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+/// First divergence between two journals, see [`diff_journals`].
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, hashmap_as_object)]
+pub struct WasmJournalDiff {
+    /// Position of the diverging command, counting only command messages.
+    pub position: u32,
+    /// Not set if the expected journal has fewer commands than the actual one.
+    pub expected: Option<WasmJournalDiffCommand>,
+    /// Not set if the actual journal has fewer commands than the expected one.
+    pub actual: Option<WasmJournalDiffCommand>,
+    /// Differences between the payload previews, empty if the command types differ or one of the commands is missing.
+    pub payload_diff: Vec<WasmPayloadFieldDiff>,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+pub struct WasmJournalDiffCommand {
+    pub message_type: u16,
+    pub message_type_name: String,
+    /// As decoded by `decode_protocol_messages`, if set.
+    pub command_index: Option<u32>,
+    pub name: Option<String>,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, hashmap_as_object)]
+pub struct WasmPayloadFieldDiff {
+    /// Path of the field in the payload preview, e.g. `3.1` for field 1 of the message in field 3.
+    pub path: String,
+    #[tsify(type = "any")]
+    pub expected: Option<JsonValue>,
+    #[tsify(type = "any")]
+    pub actual: Option<JsonValue>,
+}
+
+/// Compares the commands of two journals, returning the first diverging command, or `None` if they match.
+///
+/// Journals are lists of protocol messages, as returned by `decode_protocol_messages`,
+/// for example decoded from the inputs and outputs of a trace recorded with `WasmVMOptions.record_trace`.
+/// Non-command messages are ignored.
+#[wasm_bindgen]
+pub fn diff_journals(
+    expected: Vec<WasmProtocolMessage>,
+    actual: Vec<WasmProtocolMessage>,
+) -> Option<WasmJournalDiff> {
+    let mut expected = expected.into_iter().filter(|m| is_command(m.message_type));
+    let mut actual = actual.into_iter().filter(|m| is_command(m.message_type));
+
+    let mut position = 0;
+    loop {
+        let diff = match (expected.next(), actual.next()) {
+            (None, None) => return None,
+            (expected, actual) => diff_commands(position, expected, actual),
+        };
+        if diff.is_some() {
+            return diff;
+        }
+        position += 1;
+    }
+}
+
+fn diff_commands(
+    position: u32,
+    expected: Option<WasmProtocolMessage>,
+    actual: Option<WasmProtocolMessage>,
+) -> Option<WasmJournalDiff> {
+    let expected = expected.map(JournalCommand::from);
+    let actual = actual.map(JournalCommand::from);
+
+    let payload_diff = match (&expected, &actual) {
+        (Some(expected), Some(actual)) if expected.message_type == actual.message_type => {
+            if expected.payload == actual.payload {
+                return None;
+            }
+            let mut payload_diff = vec![];
+            diff_json(
+                String::new(),
+                Some(&expected.preview),
+                Some(&actual.preview),
+                &mut payload_diff,
+            );
+            payload_diff
+        }
+        _ => vec![],
+    };
+
+    Some(WasmJournalDiff {
+        position,
+        expected: expected.map(Into::into),
+        actual: actual.map(Into::into),
+        payload_diff,
+    })
+}
+
+struct JournalCommand {
+    message_type: u16,
+    command_index: Option<u32>,
+    name: Option<String>,
+    payload: Vec<u8>,
+    preview: JsonValue,
+}
+
+impl From<WasmProtocolMessage> for JournalCommand {
+    fn from(message: WasmProtocolMessage) -> Self {
+        // Don't trust the decoded fields, as the message might have been built by hand
+        let (name, preview) = inspect_payload(message.message_type, &message.payload);
+        Self {
+            message_type: message.message_type,
+            command_index: message.command_index,
+            name,
+            payload: message.payload.into_vec(),
+            preview,
+        }
+    }
+}
+
+impl From<JournalCommand> for WasmJournalDiffCommand {
+    fn from(command: JournalCommand) -> Self {
+        Self {
+            message_type: command.message_type,
+            message_type_name: message_type_name(command.message_type),
+            command_index: command.command_index,
+            name: command.name,
+        }
+    }
+}
+
+fn diff_json(
+    path: String,
+    expected: Option<&JsonValue>,
+    actual: Option<&JsonValue>,
+    diffs: &mut Vec<WasmPayloadFieldDiff>,
+) {
+    match (expected, actual) {
+        (Some(JsonValue::Object(expected)), Some(JsonValue::Object(actual))) => {
+            let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+            // Keys are field numbers
+            keys.sort_by_key(|k| (k.parse::<u64>().unwrap_or(u64::MAX), *k));
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                diff_json(path, expected.get(key), actual.get(key), diffs);
+            }
+        }
+        (Some(JsonValue::Array(expected)), Some(JsonValue::Array(actual))) => {
+            for i in 0..expected.len().max(actual.len()) {
+                diff_json(
+                    format!("{path}[{i}]"),
+                    expected.get(i),
+                    actual.get(i),
+                    diffs,
+                );
+            }
+        }
+        (expected, actual) if expected != actual => diffs.push(WasmPayloadFieldDiff {
+            path,
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(message_type: u16, payload: &[u8]) -> WasmProtocolMessage {
+        WasmProtocolMessage {
+            message_type,
+            flags: 0,
+            payload: serde_bytes::ByteBuf::from(payload),
+            message_type_name: None,
+            command_index: None,
+            name: None,
+            payload_preview: None,
+        }
+    }
+
+    fn journal() -> Vec<WasmProtocolMessage> {
+        vec![
+            // Start, not a command
+            message(0x0000, &[]),
+            // InputCommand with name = "greet"
+            message(0x0400, b"\x62\x05greet"),
+            // RunCompletionNotification with completion_id = 3, not a command
+            message(0x8011, &[0x08, 0x03]),
+            // OneWayCallCommand with service_name = "Greeter"
+            message(0x040E, b"\x0a\x07Greeter"),
+        ]
+    }
+
+    #[test]
+    fn identical_journals() {
+        assert!(diff_journals(journal(), journal()).is_none());
+    }
+
+    #[test]
+    fn notifications_are_ignored() {
+        let mut actual = journal();
+        actual.remove(2);
+
+        assert!(diff_journals(journal(), actual).is_none());
+    }
+
+    #[test]
+    fn command_type_mismatch() {
+        let mut actual = journal();
+        // OneWayCallCommand replaced by a CallCommand with the same payload
+        actual[3].message_type = 0x040D;
+
+        let diff = diff_journals(journal(), actual).unwrap();
+
+        assert_eq!(diff.position, 1);
+        let expected = diff.expected.unwrap();
+        assert_eq!(expected.message_type, 0x040E);
+        assert_eq!(expected.message_type_name, "OneWayCallCommand");
+        let actual = diff.actual.unwrap();
+        assert_eq!(actual.message_type, 0x040D);
+        assert_eq!(actual.message_type_name, "CallCommand");
+        assert!(diff.payload_diff.is_empty());
+    }
+
+    #[test]
+    fn payload_field_diff() {
+        let mut actual = journal();
+        // service_name = "Counter", and name = "add"
+        actual[3] = message(0x040E, b"\x0a\x07Counter\x62\x03add");
+
+        let diff = diff_journals(journal(), actual).unwrap();
+
+        assert_eq!(diff.position, 1);
+        assert_eq!(diff.expected.unwrap().name, None);
+        assert_eq!(diff.actual.unwrap().name.as_deref(), Some("add"));
+        let payload_diff: Vec<_> = diff
+            .payload_diff
+            .into_iter()
+            .map(|d| (d.path, d.expected, d.actual))
+            .collect();
+        assert_eq!(
+            payload_diff,
+            vec![
+                (
+                    "1".to_owned(),
+                    Some(json!("Greeter")),
+                    Some(json!("Counter"))
+                ),
+                ("12".to_owned(), None, Some(json!("add"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let mut actual = journal();
+        actual.truncate(3);
+
+        let diff = diff_journals(journal(), actual).unwrap();
+
+        assert_eq!(diff.position, 1);
+        assert_eq!(
+            diff.expected.unwrap().message_type_name,
+            "OneWayCallCommand"
+        );
+        assert!(diff.actual.is_none());
+        assert!(diff.payload_diff.is_empty());
+
+        let diff = diff_journals(vec![], journal()).unwrap();
+
+        assert_eq!(diff.position, 0);
+        assert!(diff.expected.is_none());
+        assert_eq!(diff.actual.unwrap().name.as_deref(), Some("greet"));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::WasmFailureMetadata;
+
+// The shared core exposes journal mismatch errors only as formatted messages,
+// so the details are extracted from the message text.
+const TYPE_MISMATCH_EXPECTED_PREFIX: &str = "recorded the following: '";
+const TYPE_MISMATCH_ACTUAL_PREFIX: &str = "attempts to perform the following: '";
+const COMMAND_MISMATCH_PREFIX: &str = "The mismatch happened while executing '";
+const COMMAND_MISMATCH_DIFF_HEADER: &str = "- Difference:";
+const AWAIT_MISMATCH_MARKER: &str = "'await' could not be replayed";
+const INDEX_PREFIX: &str = "' (index '";
+/// Indentation of the difference lines, other lines belong to multi-line values.
+const DIFF_INDENTATION: &str = "   ";
+
+/// Fields of the command messages compared by the shared core.
+const DIFF_FIELDS: &[&str] = &[
+    "awakeable_id",
+    "completion",
+    "handler_name",
+    "headers",
+    "idempotency_key",
+    "invocation_id_notification_idx",
+    "key",
+    "limit_key",
+    "name",
+    "parameter",
+    "result",
+    "result_completion_id",
+    "scope",
+    "service_name",
+    "signal_id",
+    "target",
+    "target_invocation_id",
+    "value",
+];
+/// Fields of the command messages containing the user payload.
+const PAYLOAD_FIELDS: &[&str] = &["parameter", "value", "result", "completion"];
+
+/// Metadata keys describing a journal mismatch, all prefixed by `journal_mismatch.`:
+///
+/// * `kind`: `command_type` if the command type differs, `command` if the command type matches but some of its fields differ,
+///   `await` if an await point could not be replayed, `unknown` otherwise.
+/// * `command_index`: index of the mismatching command.
+/// * `expected_command` and `actual_command`: command recorded in the journal, and command the code attempted to perform.
+/// * `payload_check`: for `command` mismatches, `failed` if the payload differs, `passed` otherwise.
+/// * `diff.<field>`: for `command` mismatches, `<actual> != <expected>` for each differing field.
+pub(crate) fn journal_mismatch_metadata(message: &str) -> Vec<WasmFailureMetadata> {
+    let mut metadata = vec![];
+
+    if let (Some((expected, command_index)), Some(actual)) = (
+        find_command_and_index(message, TYPE_MISMATCH_EXPECTED_PREFIX),
+        find_quoted(message, TYPE_MISMATCH_ACTUAL_PREFIX),
+    ) {
+        push(&mut metadata, "kind", "command_type");
+        push(&mut metadata, "command_index", command_index);
+        push(&mut metadata, "expected_command", expected);
+        push(&mut metadata, "actual_command", actual);
+    } else if let Some((command, command_index)) =
+        find_command_and_index(message, COMMAND_MISMATCH_PREFIX)
+    {
+        push(&mut metadata, "kind", "command");
+        push(&mut metadata, "command_index", command_index);
+        push(&mut metadata, "expected_command", command);
+        push(&mut metadata, "actual_command", command);
+
+        let diffs = message
+            .split_once(COMMAND_MISMATCH_DIFF_HEADER)
+            .map(|(_, diffs)| parse_diffs(diffs))
+            .unwrap_or_default();
+        let payload_check = if diffs
+            .iter()
+            .any(|(field, _)| PAYLOAD_FIELDS.contains(field))
+        {
+            "failed"
+        } else {
+            "passed"
+        };
+        push(&mut metadata, "payload_check", payload_check);
+        for (field, diff) in diffs {
+            push(&mut metadata, &format!("diff.{field}"), &diff);
+        }
+    } else if message.contains(AWAIT_MISMATCH_MARKER) {
+        push(&mut metadata, "kind", "await");
+    } else {
+        push(&mut metadata, "kind", "unknown");
+    }
+
+    metadata
+}
+
+fn push(metadata: &mut Vec<WasmFailureMetadata>, key: &str, value: &str) {
+    metadata.push(WasmFailureMetadata {
+        key: format!("journal_mismatch.{key}"),
+        value: value.to_owned(),
+    });
+}
+
+/// Parses the `<field>: <diff>` lines following the difference header.
+/// Lines which don't start a known field belong to the multi-line value of the previous one.
+fn parse_diffs(diffs: &str) -> Vec<(&str, String)> {
+    let mut res: Vec<(&str, String)> = vec![];
+    for line in diffs.lines() {
+        match line
+            .strip_prefix(DIFF_INDENTATION)
+            .and_then(|line| line.split_once(": "))
+        {
+            Some((field, diff)) if DIFF_FIELDS.contains(&field) => {
+                res.push((field, diff.to_owned()))
+            }
+            _ => {
+                if let Some((_, diff)) = res.last_mut() {
+                    diff.push('\n');
+                    diff.push_str(line);
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Parses `<prefix><command>' (index '<index>')`.
+fn find_command_and_index<'a>(message: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let (_, rest) = message.split_once(prefix)?;
+    let (command, rest) = rest.split_once(INDEX_PREFIX)?;
+    let (index, _) = rest.split_once('\'')?;
+    Some((command, index))
+}
+
+/// Parses `<prefix><value>'` at the end of the line.
+fn find_quoted<'a>(message: &'a str, prefix: &str) -> Option<&'a str> {
+    let (_, rest) = message.split_once(prefix)?;
+    let line = rest.lines().next()?;
+    line.strip_suffix('\'')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same formats as the shared core errors
+    fn type_mismatch_message(expected: &str, command_index: i64, actual: &str) -> String {
+        format!("Found a mismatch between the code paths taken during the previous execution and the paths taken during this execution.
+This typically happens when some parts of the code are non-deterministic.
+ - The previous execution ran and recorded the following: '{expected}' (index '{command_index}')
+ - The current execution attempts to perform the following: '{actual}'")
+    }
+
+    fn command_mismatch_message(
+        command: &str,
+        command_index: i64,
+        diffs: &[(&str, &str)],
+    ) -> String {
+        let mut message = format!("Found a mismatch between the code paths taken during the previous execution and the paths taken during this execution.
+This typically happens when some parts of the code are non-deterministic.
+- The mismatch happened while executing '{command}' (index '{command_index}')
+- Difference:");
+        for (field, diff) in diffs {
+            message.push_str(&format!("\n   {field}: {diff}"));
+        }
+        message
+    }
+
+    fn pairs(metadata: &[WasmFailureMetadata]) -> Vec<(&str, &str)> {
+        metadata
+            .iter()
+            .map(|m| (m.key.as_str(), m.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn command_type_mismatch() {
+        let message = type_mismatch_message("call", 2, "one way call/send");
+
+        assert_eq!(
+            pairs(&journal_mismatch_metadata(&message)),
+            [
+                ("journal_mismatch.kind", "command_type"),
+                ("journal_mismatch.command_index", "2"),
+                ("journal_mismatch.expected_command", "call"),
+                ("journal_mismatch.actual_command", "one way call/send"),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_mismatch_with_payload_diff() {
+        let message = command_mismatch_message(
+            "call",
+            5,
+            &[
+                ("service_name", "Greeter != Counter"),
+                (
+                    "parameter",
+                    "'{\"name\": \"Till\"}' != '{\"name\": \"Francesco\"}'",
+                ),
+            ],
+        );
+
+        assert_eq!(
+            pairs(&journal_mismatch_metadata(&message)),
+            [
+                ("journal_mismatch.kind", "command"),
+                ("journal_mismatch.command_index", "5"),
+                ("journal_mismatch.expected_command", "call"),
+                ("journal_mismatch.actual_command", "call"),
+                ("journal_mismatch.payload_check", "failed"),
+                ("journal_mismatch.diff.service_name", "Greeter != Counter"),
+                (
+                    "journal_mismatch.diff.parameter",
+                    "'{\"name\": \"Till\"}' != '{\"name\": \"Francesco\"}'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_mismatch_keeps_lines_of_multi_line_values() {
+        let message = command_mismatch_message(
+            "set state",
+            1,
+            &[
+                (
+                    "value",
+                    "'first line\nkey: not a field\n   other: not a field' != <empty>",
+                ),
+                ("key", "'a' != 'b'"),
+            ],
+        );
+
+        assert_eq!(
+            pairs(&journal_mismatch_metadata(&message)),
+            [
+                ("journal_mismatch.kind", "command"),
+                ("journal_mismatch.command_index", "1"),
+                ("journal_mismatch.expected_command", "set state"),
+                ("journal_mismatch.actual_command", "set state"),
+                ("journal_mismatch.payload_check", "failed"),
+                (
+                    "journal_mismatch.diff.value",
+                    "'first line\nkey: not a field\n   other: not a field' != <empty>"
+                ),
+                ("journal_mismatch.diff.key", "'a' != 'b'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_mismatch_without_payload_diff() {
+        let message = command_mismatch_message("sleep", 0, &[("name", "'a' != 'b'")]);
+
+        let metadata = journal_mismatch_metadata(&message);
+
+        assert_eq!(
+            pairs(&metadata[4..]),
+            [
+                ("journal_mismatch.payload_check", "passed"),
+                ("journal_mismatch.diff.name", "'a' != 'b'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn await_mismatch() {
+        let message = "Found a mismatch between the code paths taken during the previous execution and the paths taken during this execution.
+'await' could not be replayed. This usually means the code was mutated adding an 'await' without registering a new service revision.
+Notifications awaited on this await point:
+ - completion id 1";
+
+        assert_eq!(
+            pairs(&journal_mismatch_metadata(message)),
+            [("journal_mismatch.kind", "await")]
+        );
+    }
+
+    #[test]
+    fn unknown_mismatch() {
+        assert_eq!(
+            pairs(&journal_mismatch_metadata("Something else: happened")),
+            [("journal_mismatch.kind", "unknown")]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use crate::WasmLogContext;
+use serde_json::{Map, Number, Value as JsonValue};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Event formatter writing each event as a single line JSON record.
+///
+/// The record contains the `level`, the `target`, the event `fields` (including the `message`)
+/// and the `spans` stack, from the root span to the leaf span, each with its own fields.
+/// When the formatter belongs to a VM, the invocation `context` is added on delivery, see [`append_json_context`].
+#[derive(Default, Clone, Copy)]
+pub struct JsonFormat;
+
+impl<S> FormatEvent<S, JsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let meta = event.metadata();
+
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        let mut spans = vec![];
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                let mut span_record = Map::new();
+                span_record.insert("name".to_owned(), span.name().into());
+                if let Some(formatted_fields) =
+                    span.extensions().get::<FormattedFields<JsonFields>>()
+                {
+                    if let Ok(JsonValue::Object(span_fields)) =
+                        serde_json::from_str::<JsonValue>(&formatted_fields.fields)
+                    {
+                        span_record.insert("fields".to_owned(), span_fields.into());
+                    }
+                }
+                spans.push(JsonValue::Object(span_record));
+            }
+        }
+
+        let mut record = Map::new();
+        record.insert("level".to_owned(), meta.level().as_str().into());
+        record.insert("target".to_owned(), meta.target().into());
+        record.insert("fields".to_owned(), fields.into());
+        record.insert("spans".to_owned(), spans.into());
+
+        writeln!(
+            writer,
+            "{}",
+            serde_json::to_string(&record).map_err(|_| fmt::Error)?
+        )
+    }
+}
+
+/// Adds the invocation `context` to a record written by [`JsonFormat`].
+///
+/// The context is known only when the record is delivered, after the VM call emitting it returns.
+pub(crate) fn append_json_context(record: &mut Vec<u8>, context: &WasmLogContext) {
+    if record.last() != Some(&b'}') {
+        return;
+    }
+    let Ok(context) = serde_json::to_vec(context) else {
+        return;
+    };
+    record.pop();
+    record.extend_from_slice(b",\"context\":");
+    record.extend_from_slice(&context);
+    record.push(b'}');
+}
+
+/// Field formatter storing span fields as a JSON object, to be used together with [`JsonFormat`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut map = Map::new();
+        fields.record(&mut JsonVisitor(&mut map));
+        write!(
+            writer,
+            "{}",
+            serde_json::to_string(&map).map_err(|_| fmt::Error)?
+        )
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut map = match serde_json::from_str::<JsonValue>(&current.fields) {
+            Ok(JsonValue::Object(map)) => map,
+            _ => Map::new(),
+        };
+        fields.record(&mut JsonVisitor(&mut map));
+        current.fields = serde_json::to_string(&map).map_err(|_| fmt::Error)?;
+        Ok(())
+    }
+}
+
+/// Largest integer that JavaScript can represent exactly, bigger integers are recorded as strings.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+pub(crate) struct JsonVisitor<'a>(pub(crate) &'a mut Map<String, JsonValue>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(
+            field.name().to_owned(),
+            Number::from_f64(value)
+                .map(JsonValue::Number)
+                .unwrap_or_else(|| value.to_string().into()),
+        );
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if value.unsigned_abs() > MAX_SAFE_INTEGER {
+            self.0
+                .insert(field.name().to_owned(), value.to_string().into());
+        } else {
+            self.0.insert(field.name().to_owned(), value.into());
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if value > MAX_SAFE_INTEGER {
+            self.0
+                .insert(field.name().to_owned(), value.to_string().into());
+        } else {
+            self.0.insert(field.name().to_owned(), value.into());
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0
+            .insert(field.name().to_owned(), value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_owned(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn records(f: impl FnOnce()) -> Vec<JsonValue> {
+        let writer = TestWriter::default();
+        let subscriber = tracing_subscriber::fmt()
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer({
+                let writer = writer.clone();
+                move || writer.clone()
+            })
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+
+        let output = writer.0.lock().unwrap();
+        String::from_utf8_lossy(&output)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn event_with_span_stack() {
+        let records = records(|| {
+            let outer = tracing::info_span!("invoke", service = "Greeter");
+            let _outer = outer.enter();
+            let inner = tracing::info_span!("run", name = "charge");
+            let _inner = inner.enter();
+            inner.record("name", "charge-card");
+            tracing::warn!(attempt = 2, "retrying");
+        });
+
+        assert_eq!(
+            records,
+            [serde_json::json!({
+                "level": "WARN",
+                "target": module_path!(),
+                "fields": { "message": "retrying", "attempt": 2 },
+                "spans": [
+                    { "name": "invoke", "fields": { "service": "Greeter" } },
+                    { "name": "run", "fields": { "name": "charge-card" } },
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn field_values() {
+        let records = records(|| {
+            tracing::info!(
+                small = 42u64,
+                negative = -42i64,
+                big = u64::MAX,
+                big_negative = i64::MIN,
+                float = 1.5f64,
+                nan = f64::NAN,
+                flag = true,
+                text = "hello",
+                debug = ?Some(1),
+                "values"
+            );
+        });
+
+        assert_eq!(
+            records[0]["fields"],
+            serde_json::json!({
+                "message": "values",
+                "small": 42,
+                "negative": -42,
+                "big": u64::MAX.to_string(),
+                "big_negative": i64::MIN.to_string(),
+                "float": 1.5,
+                "nan": "NaN",
+                "flag": true,
+                "text": "hello",
+                "debug": "Some(1)",
+            })
+        );
+    }
+
+    #[test]
+    fn context_is_appended_only_to_json_objects() {
+        let mut context = WasmLogContext::new(Some("inv".to_owned()));
+        context.command_index = 3;
+
+        let mut record = br#"{"level":"INFO"}"#.to_vec();
+        append_json_context(&mut record, &context);
+        assert_eq!(
+            serde_json::from_slice::<JsonValue>(&record).unwrap(),
+            serde_json::json!({
+                "level": "INFO",
+                "context": {
+                    "invocation_id": "inv",
+                    "command_index": 3,
+                    "state": "WaitingPreFlight",
+                },
+            })
+        );
+
+        let mut record = b"not json".to_vec();
+        append_json_context(&mut record, &context);
+        assert_eq!(record, b"not json");
+    }
+}
@@ -1,23 +1,47 @@
+mod error_details;
+mod failure_causes;
+mod journal_diff;
+mod journal_mismatch;
+mod json_format;
+mod panic_isolation;
+mod panic_report;
+mod protocol;
+mod span_export;
+mod trace;
+mod trace_context;
+
+use crate::error_details::WasmErrorKind;
+use crate::failure_causes::{decode_causes, encode_causes, WasmFailureCause};
+use crate::journal_mismatch::journal_mismatch_metadata;
+use crate::json_format::{append_json_context, JsonFields, JsonFormat};
+use crate::panic_report::{flush_active_vm_logs, ActiveVMGuard, WasmPanicReport};
+use crate::span_export::{SpanExportLayer, WasmSpanEvent};
+use crate::trace::{trace_headers, trace_now, InvocationTrace, TraceEvent};
+use crate::trace_context::TraceContext;
+use bytes::Bytes;
 use js_sys::Uint8Array;
+use restate_sdk_shared_core::error::codes;
 use restate_sdk_shared_core::tracing_pretty::{Pretty, PrettyFields};
 use restate_sdk_shared_core::{
-    AwaitResponse, AwakeableHandle, CallHandle, CommandRelationship, CommandType, CoreVM, Error,
-    Header, HeaderMap, IdentityVerifier, ImplicitCancellationOption, Input,
+    AwaitResponse, AwaitingOnPolicy, AwakeableHandle, CallHandle, CommandRelationship, CommandType,
+    CoreVM, Error, Header, HeaderMap, IdentityVerifier, ImplicitCancellationOption, Input,
     JournalMismatchRetryBehavior, NonDeterministicChecksOption, NonEmptyValue, OnMaxAttempts,
-    ResponseHead, RetryPolicy, RunExitResult, RunHandle, SendHandle, Target, TerminalFailure,
-    UnresolvedFuture, VMOptions, Value, CANCEL_NOTIFICATION_HANDLE, VM,
+    PayloadOptions, ResponseHead, RetryPolicy, RunExitResult, RunHandle, SendHandle, State, Target,
+    TerminalFailure, UnresolvedFuture, VMOptions, Value, CANCEL_NOTIFICATION_HANDLE, VM,
 };
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::cmp;
 use std::convert::{Infallible, Into};
 use std::io::Write;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::metadata::LevelFilter;
 use tracing::{Dispatch, Level, Subscriber};
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::{Layer, Registry};
+use tracing_subscriber::{reload, Layer, Registry};
 use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
@@ -26,11 +50,13 @@ use wasm_bindgen::prelude::*;
 pub fn start() {
     // Setup our panic hook
     std::panic::set_hook(Box::new(|info| {
-        fatal(&info.to_string());
+        flush_active_vm_logs();
+        fatal(WasmPanicReport::new(info));
     }));
 }
 
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub enum LogLevel {
     TRACE = 0,
     DEBUG = 1,
@@ -39,8 +65,33 @@ pub enum LogLevel {
     ERROR = 4,
 }
 
+impl LogLevel {
+    fn parse(value: &str) -> Option<LogLevel> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "trace" => Some(LogLevel::TRACE),
+            "debug" => Some(LogLevel::DEBUG),
+            "info" => Some(LogLevel::INFO),
+            "warn" => Some(LogLevel::WARN),
+            "error" => Some(LogLevel::ERROR),
+            _ => None,
+        }
+    }
+}
+
+/// Format of the log records sent to `vm_log`.
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy, Default)]
+#[tsify(from_wasm_abi)]
+pub enum LogFormat {
+    /// Human readable text. At TRACE level this uses the pretty multi-line format.
+    #[default]
+    Text,
+    /// One JSON record per event, containing level, target, span stack and event fields.
+    Json,
+}
+
 /// How the state machine should behave when it hits a journal mismatch (non-determinism) error.
 #[wasm_bindgen]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum WasmJournalMismatchBehavior {
     /// Follow the normal retry policy.
     Retry = 0,
@@ -63,15 +114,244 @@ impl From<WasmJournalMismatchBehavior> for JournalMismatchRetryBehavior {
 #[wasm_bindgen(raw_module = "../core_logging.js")]
 extern "C" {
     #[wasm_bindgen]
-    fn vm_log(level: LogLevel, s: &[u8], logger_id: Option<u32>);
+    fn vm_log(level: LogLevel, s: &[u8], logger_id: Option<u32>, context: Option<WasmLogContext>);
+
+    /// Called with the buffered log records, when log batching is enabled
+    #[wasm_bindgen]
+    fn vm_log_batch(records: Vec<WasmLogRecord>, logger_id: Option<u32>);
+
+    /// Called on span open, record and close, when span export is enabled
+    #[wasm_bindgen]
+    fn vm_span_event(event: WasmSpanEvent, logger_id: Option<u32>);
 
     /// Called on panics
     #[wasm_bindgen]
-    fn fatal(s: &str);
+    fn fatal(report: WasmPanicReport);
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// Invocation context attached to each log record emitted by a [`WasmVM`].
+///
+/// It's read from the VM when the record is delivered, see [`MakeWebConsoleWriter::begin_call`].
+#[derive(Tsify, Serialize, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct WasmLogContext {
+    pub invocation_id: Option<String>,
+    /// Same as [`WasmVM::last_command_index`].
+    pub command_index: i32,
+    pub state: &'static str,
+}
+
+impl WasmLogContext {
+    fn new(invocation_id: Option<String>) -> Self {
+        Self {
+            invocation_id,
+            command_index: -1,
+            state: state_name(State::WaitingPreFlight),
+        }
+    }
+
+    fn refresh(&mut self, vm: &CoreVM) {
+        self.command_index = vm.last_command_index() as i32;
+        self.state = state_name(vm.state());
+    }
+
+    fn is_replaying(&self) -> bool {
+        self.state == state_name(State::Replaying)
+    }
+}
+
+fn state_name(state: State) -> &'static str {
+    match state {
+        State::WaitingPreFlight => "WaitingPreFlight",
+        State::Replaying => "Replaying",
+        State::Processing => "Processing",
+        State::Closed => "Closed",
+    }
+}
+
+type SharedLogContext = Arc<Mutex<WasmLogContext>>;
+
+/// Log record delivered through `vm_log_batch`.
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+pub struct WasmLogRecord {
+    /// One of the [`LogLevel`] values.
+    pub level: u8,
+    #[tsify(type = "Uint8Array")]
+    pub message: serde_bytes::ByteBuf,
+    pub context: Option<WasmLogContext>,
+}
+
+/// Buffer of log records, flushed to `vm_log_batch` either explicitly or when reaching `max_records`.
+struct LogBuffer {
+    logger_id: Option<u32>,
+    records: Vec<WasmLogRecord>,
+    max_records: usize,
+}
+
+impl LogBuffer {
+    fn new(logger_id: Option<u32>, max_records: usize) -> Self {
+        Self {
+            logger_id,
+            records: Vec::with_capacity(max_records),
+            max_records,
+        }
+    }
+}
+
+type SharedLogBuffer = Arc<Mutex<LogBuffer>>;
+
+fn push_log_record(log_buffer: &SharedLogBuffer, record: WasmLogRecord) {
+    let should_flush = if let Ok(mut log_buffer) = log_buffer.lock() {
+        log_buffer.records.push(record);
+        log_buffer.records.len() >= log_buffer.max_records
+    } else {
+        false
+    };
+    if should_flush {
+        flush_log_records(log_buffer);
+    }
+}
+
+fn flush_log_records(log_buffer: &SharedLogBuffer) {
+    // Don't invoke JS while holding the lock
+    let (records, logger_id) = if let Ok(mut log_buffer) = log_buffer.lock() {
+        let max_records = log_buffer.max_records;
+        (
+            std::mem::replace(&mut log_buffer.records, Vec::with_capacity(max_records)),
+            log_buffer.logger_id,
+        )
+    } else {
+        return;
+    };
+    if !records.is_empty() {
+        vm_log_batch(records, logger_id);
+    }
+}
+
+/// Log record emitted while a [`WasmVM`] call is in progress, see [`MakeWebConsoleWriter::begin_call`].
+struct PendingLogRecord {
+    level: LogLevel,
+    message: Vec<u8>,
+    json: bool,
 }
 
+/// Set while a [`WasmVM`] call is in progress.
+type SharedPendingLogs = Arc<Mutex<Option<Vec<PendingLogRecord>>>>;
+
+#[derive(Clone)]
 pub struct MakeWebConsoleWriter {
     logger_id: Option<u32>,
+    context: Option<SharedLogContext>,
+    buffer: Option<SharedLogBuffer>,
+    pending: Option<SharedPendingLogs>,
+    drop_replay_logs: bool,
+    json: bool,
+}
+
+impl MakeWebConsoleWriter {
+    fn new(logger_id: Option<u32>) -> Self {
+        Self {
+            logger_id,
+            context: None,
+            buffer: None,
+            pending: None,
+            drop_replay_logs: false,
+            json: false,
+        }
+    }
+
+    /// The shared core holds the VM for the whole duration of a call, so the invocation context cannot be read
+    /// while the call is in progress. The records emitted in the meantime are delivered by the returned guard,
+    /// with the context read from the VM once the call returns.
+    fn begin_call(&self) -> PendingLogsGuard<'_> {
+        if let Some(Ok(mut pending)) = self.pending.as_ref().map(|p| p.lock()) {
+            pending.get_or_insert_with(Vec::new);
+        }
+        PendingLogsGuard(self)
+    }
+
+    fn write_record(&self, level: LogLevel, message: &[u8]) {
+        if let Some(Ok(mut pending)) = self.pending.as_ref().map(|p| p.lock()) {
+            if let Some(records) = pending.as_mut() {
+                records.push(PendingLogRecord {
+                    level,
+                    message: message.to_vec(),
+                    json: self.json,
+                });
+                return;
+            }
+        }
+        self.deliver(level, message.to_vec(), self.json);
+    }
+
+    fn deliver_pending(&self) {
+        // Don't invoke JS while holding the lock
+        let records = match self.pending.as_ref().map(|p| p.try_lock()) {
+            Some(Ok(mut pending)) => pending.take().unwrap_or_default(),
+            _ => return,
+        };
+        for PendingLogRecord {
+            level,
+            message,
+            json,
+        } in records
+        {
+            self.deliver(level, message, json);
+        }
+    }
+
+    /// Delivers the records of the call in progress right away, with the context known before the call.
+    ///
+    /// This runs in the panic hook: with `panic = "abort"` the call never returns,
+    /// so the records leading to the panic would be lost otherwise.
+    pub(crate) fn flush_on_panic(&self) {
+        self.deliver_pending();
+        if let Some(log_buffer) = &self.buffer {
+            flush_log_records(log_buffer);
+        }
+    }
+
+    fn deliver(&self, level: LogLevel, mut message: Vec<u8>, json: bool) {
+        let context = self
+            .context
+            .as_ref()
+            .and_then(|context| context.lock().ok().map(|context| context.clone()));
+        if self.drop_replay_logs && context.as_ref().is_some_and(WasmLogContext::is_replaying) {
+            return;
+        }
+        if let (Some(context), true) = (&context, json) {
+            append_json_context(&mut message, context);
+        }
+
+        if let Some(log_buffer) = &self.buffer {
+            push_log_record(
+                log_buffer,
+                WasmLogRecord {
+                    level: level as u8,
+                    message: message.into(),
+                    context,
+                },
+            )
+        } else {
+            vm_log(level, &message, self.logger_id, context)
+        }
+    }
+}
+
+/// Delivers the records emitted during a [`WasmVM`] call when dropped, see [`MakeWebConsoleWriter::begin_call`].
+struct PendingLogsGuard<'a>(&'a MakeWebConsoleWriter);
+
+impl Drop for PendingLogsGuard<'_> {
+    fn drop(&mut self) {
+        self.0.deliver_pending();
+    }
 }
 
 impl<'a> MakeWriter<'a> for MakeWebConsoleWriter {
@@ -81,7 +361,7 @@ impl<'a> MakeWriter<'a> for MakeWebConsoleWriter {
         ConsoleWriter {
             buffer: vec![],
             level: Level::TRACE, // if no level is known, assume the most detailed
-            logger_id: self.logger_id,
+            writer: self.clone(),
         }
     }
 
@@ -90,7 +370,7 @@ impl<'a> MakeWriter<'a> for MakeWebConsoleWriter {
         ConsoleWriter {
             buffer: vec![],
             level,
-            logger_id: self.logger_id,
+            writer: self.clone(),
         }
     }
 }
@@ -99,7 +379,7 @@ pub struct ConsoleWriter {
     buffer: Vec<u8>,
 
     level: Level,
-    logger_id: Option<u32>,
+    writer: MakeWebConsoleWriter,
 }
 
 impl Write for ConsoleWriter {
@@ -114,45 +394,121 @@ impl Write for ConsoleWriter {
 
 impl Drop for ConsoleWriter {
     fn drop(&mut self) {
-        vm_log(
-            match self.level {
-                Level::TRACE => LogLevel::TRACE,
-                Level::DEBUG => LogLevel::DEBUG,
-                Level::INFO => LogLevel::INFO,
-                Level::WARN => LogLevel::WARN,
-                Level::ERROR => LogLevel::ERROR,
-            },
-            // Remove last character, which is always a new line
-            &self.buffer[..cmp::max(0, self.buffer.len() - 1)],
-            self.logger_id,
-        )
+        let level = match self.level {
+            Level::TRACE => LogLevel::TRACE,
+            Level::DEBUG => LogLevel::DEBUG,
+            Level::INFO => LogLevel::INFO,
+            Level::WARN => LogLevel::WARN,
+            Level::ERROR => LogLevel::ERROR,
+        };
+        // Remove last character, which is always a new line
+        let message = &self.buffer[..cmp::max(0, self.buffer.len() - 1)];
+        self.writer.write_record(level, message);
     }
 }
 
-/// This will set the log level of the overall log subscriber.
+static GLOBAL_LOG_RELOADER: Mutex<Option<LogReloader>> = Mutex::new(None);
+
+/// This will set the log level and format of the overall log subscriber.
+///
+/// Can be invoked several times, the latest invocation wins.
 #[wasm_bindgen]
-pub fn set_log_level(level: LogLevel) {
-    let _ = tracing::subscriber::set_global_default(log_subscriber(level, None));
+pub fn set_log_level(level: LogLevel, format: Option<LogFormat>) {
+    let Ok(mut global_log_reloader) = GLOBAL_LOG_RELOADER.lock() else {
+        return;
+    };
+    let format = format.unwrap_or_default();
+
+    if let Some(log_reloader) = global_log_reloader.as_mut() {
+        log_reloader.reload(level, format);
+    } else {
+        let (subscriber, log_reloader) =
+            log_subscriber(level, format, MakeWebConsoleWriter::new(None), None);
+        if tracing::subscriber::set_global_default(subscriber).is_ok() {
+            *global_log_reloader = Some(log_reloader);
+        }
+    }
+}
+
+type LogLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Rebuilds the log layer of a subscriber created with [`log_subscriber`] when the level or the format changes.
+struct LogReloader {
+    layer_handle: reload::Handle<LogLayer, Registry>,
+    filter_handle: reload::Handle<LevelFilter, Registry>,
+    format: LogFormat,
+    writer: MakeWebConsoleWriter,
+}
+
+impl LogReloader {
+    fn set_level(&mut self, level: LogLevel) {
+        self.reload(level, self.format);
+    }
+
+    fn reload(&mut self, level: LogLevel, format: LogFormat) {
+        self.format = format;
+        let _ = self
+            .layer_handle
+            .reload(log_layer(level, format, self.writer.clone()));
+        let _ = self.filter_handle.reload(level_filter(level));
+    }
 }
 
 fn log_subscriber(
     level: LogLevel,
-    logger_id: Option<u32>,
-) -> impl Subscriber + Send + Sync + 'static {
-    let level = match level {
-        LogLevel::TRACE => Level::TRACE,
-        LogLevel::DEBUG => Level::DEBUG,
-        LogLevel::INFO => Level::INFO,
-        LogLevel::WARN => Level::WARN,
-        LogLevel::ERROR => Level::ERROR,
-    };
+    format: LogFormat,
+    writer: MakeWebConsoleWriter,
+    span_export: Option<SpanExportLayer>,
+) -> (impl Subscriber + Send + Sync + 'static, LogReloader) {
+    let (layer, layer_handle) = reload::Layer::new(log_layer(level, format, writer.clone()));
+    let (filter, filter_handle) = reload::Layer::new(level_filter(level));
+
+    (
+        Registry::default()
+            .with(
+                layer
+                    // We do filtering here too,
+                    // as it might get expensive to pass logs through
+                    // the various layers even though we don't need them
+                    .with_filter(filter),
+            )
+            // Core spans are all emitted at TRACE level, regardless of the log level.
+            // Only wrap the layer when present, otherwise the Dispatch would advertise TRACE.
+            .with(span_export.map(|layer| layer.with_filter(LevelFilter::TRACE))),
+        LogReloader {
+            layer_handle,
+            filter_handle,
+            format,
+            writer,
+        },
+    )
+}
 
-    let fmt_layer = if level == Level::TRACE {
+fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::TRACE => LevelFilter::TRACE,
+        LogLevel::DEBUG => LevelFilter::DEBUG,
+        LogLevel::INFO => LevelFilter::INFO,
+        LogLevel::WARN => LevelFilter::WARN,
+        LogLevel::ERROR => LevelFilter::ERROR,
+    }
+}
+
+fn log_layer(level: LogLevel, format: LogFormat, mut writer: MakeWebConsoleWriter) -> LogLayer {
+    writer.json = matches!(format, LogFormat::Json);
+    if writer.json {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(writer)
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .boxed()
+    } else if matches!(level, LogLevel::TRACE) {
         tracing_subscriber::fmt::layer()
             .with_ansi(false)
             .without_time()
             .with_span_events(FmtSpan::ENTER)
-            .with_writer(MakeWebConsoleWriter { logger_id })
+            .with_writer(writer)
             .event_format(
                 Pretty::default()
                     .without_time()
@@ -161,7 +517,7 @@ fn log_subscriber(
                     .with_target(true)
                     .with_level(true),
             )
-            .fmt_fields(PrettyFields::default())
+            .fmt_fields(PrettyFields)
             .boxed()
     } else {
         tracing_subscriber::fmt::layer()
@@ -174,22 +530,15 @@ fn log_subscriber(
             .with_target(false)
             .with_level(false)
             .with_span_events(FmtSpan::NONE)
-            .with_writer(MakeWebConsoleWriter { logger_id })
+            .with_writer(writer)
             .boxed()
-    };
-
-    Registry::default().with(
-        fmt_layer
-            // We do filtering here too,
-            // as it might get expensive to pass logs through
-            // the various layers even though we don't need them
-            .with_filter(LevelFilter::from_level(level)),
-    )
+    }
 }
 
 //--- Data model
 
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub enum WasmCommandType {
     Input,
     Output,
@@ -238,6 +587,52 @@ impl From<WasmCommandType> for CommandType {
     }
 }
 
+/// Serializes [`WasmCommandType`] as its discriminant, as JS passes wasm_bindgen enums as numbers.
+mod command_type_discriminant {
+    use super::WasmCommandType;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const COMMAND_TYPES: [WasmCommandType; 19] = [
+        WasmCommandType::Input,
+        WasmCommandType::Output,
+        WasmCommandType::GetState,
+        WasmCommandType::GetStateKeys,
+        WasmCommandType::SetState,
+        WasmCommandType::ClearState,
+        WasmCommandType::ClearAllState,
+        WasmCommandType::GetPromise,
+        WasmCommandType::PeekPromise,
+        WasmCommandType::CompletePromise,
+        WasmCommandType::Sleep,
+        WasmCommandType::Call,
+        WasmCommandType::OneWayCall,
+        WasmCommandType::SendSignal,
+        WasmCommandType::Run,
+        WasmCommandType::AttachInvocation,
+        WasmCommandType::GetInvocationOutput,
+        WasmCommandType::CompleteAwakeable,
+        WasmCommandType::CancelInvocation,
+    ];
+
+    pub(crate) fn serialize<S: Serializer>(
+        command_type: &WasmCommandType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*command_type as u32)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<WasmCommandType, D::Error> {
+        let discriminant = u32::deserialize(deserializer)?;
+        COMMAND_TYPES
+            .get(discriminant as usize)
+            .copied()
+            .ok_or_else(|| D::Error::custom(format!("unknown WasmCommandType {discriminant}")))
+    }
+}
+
 #[wasm_bindgen(getter_with_clone)]
 #[derive(Clone)]
 pub struct WasmHeader {
@@ -298,32 +693,56 @@ impl From<ResponseHead> for WasmResponseHead {
 
 type WasmNotificationHandle = u32;
 
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmFailureMetadata {
     pub key: String,
     pub value: String,
 }
 
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmFailure {
     pub code: u16,
     pub message: String,
     pub metadata: Vec<WasmFailureMetadata>,
+    /// Chain of causes, starting from the direct cause up to the root cause.
+    /// Causes are recorded in the journal, and survive the round trip through other services.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub causes: Vec<WasmFailureCause>,
+    /// Set for failures returned by the VM, see `is_suspended` and `is_retryable`.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub kind: Option<WasmErrorKind>,
+
+    /// Set only when converting errors of the shared core, ignored when the failure is recorded in the journal.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub stacktrace: Option<String>,
 }
 
 impl From<Error> for WasmFailure {
     fn from(value: Error) -> Self {
+        let metadata = if value.code() == codes::JOURNAL_MISMATCH.code() {
+            journal_mismatch_metadata(value.message())
+        } else {
+            vec![]
+        };
         WasmFailure {
             code: value.code(),
-            message: value.to_string(),
-            metadata: vec![],
+            message: value.message().to_owned(),
+            metadata,
+            causes: vec![],
+            kind: Some(WasmErrorKind::from_code(value.code())),
+            stacktrace: Some(value.description())
+                .filter(|stacktrace| !stacktrace.is_empty())
+                .map(ToOwned::to_owned),
         }
     }
 }
 
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmExponentialRetryConfig {
     pub initial_interval: Option<u64>,
@@ -331,6 +750,10 @@ pub struct WasmExponentialRetryConfig {
     pub max_interval: Option<u64>,
     pub max_attempts: Option<u32>,
     pub max_duration: Option<u64>,
+    /// What to do once `max_attempts` or `max_duration` is reached, defaults to failing the run.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub on_max_attempts: WasmOnMaxAttempts,
 }
 
 impl From<WasmExponentialRetryConfig> for RetryPolicy {
@@ -341,35 +764,251 @@ impl From<WasmExponentialRetryConfig> for RetryPolicy {
             max_duration: value.max_duration.map(Duration::from_millis),
             factor: value.factor,
             max_interval: value.max_interval.map(Duration::from_millis),
-            on_max_attempts: OnMaxAttempts::FailAsTerminal,
+            on_max_attempts: value.on_max_attempts.into(),
+        }
+    }
+}
+
+/// Options for the syscalls with a payload, e.g. [`WasmVM::sys_set_state`].
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy, Default)]
+#[tsify(from_wasm_abi)]
+#[serde(default)]
+pub struct WasmPayloadOptions {
+    /// If true, the payload is serialized non-deterministically,
+    /// and it's not compared with the journal during replay.
+    #[tsify(optional)]
+    pub unstable_serialization: bool,
+}
+
+impl From<WasmPayloadOptions> for PayloadOptions {
+    fn from(value: WasmPayloadOptions) -> Self {
+        if value.unstable_serialization {
+            PayloadOptions::unstable_serialization()
+        } else {
+            PayloadOptions::stable_serialization()
+        }
+    }
+}
+
+/// Retry policy of a run, see [`WasmVM::propose_run_completion`].
+#[derive(Tsify, Serialize, Deserialize, Clone)]
+#[tsify(from_wasm_abi)]
+pub enum WasmRetryPolicy {
+    /// Retry forever, following the retry policy of the runtime.
+    Infinite,
+    /// Fail on the first failure.
+    None,
+    /// Durations are in millis.
+    FixedDelay {
+        /// If not set, the runtime picks the interval based on its retry policy.
+        #[tsify(optional)]
+        #[serde(default)]
+        interval: Option<u64>,
+        #[tsify(optional)]
+        #[serde(default)]
+        max_attempts: Option<u32>,
+        #[tsify(optional)]
+        #[serde(default)]
+        max_duration: Option<u64>,
+        #[tsify(optional)]
+        #[serde(default)]
+        on_max_attempts: WasmOnMaxAttempts,
+    },
+    Exponential(WasmExponentialRetryConfig),
+}
+
+impl From<WasmRetryPolicy> for RetryPolicy {
+    fn from(value: WasmRetryPolicy) -> Self {
+        match value {
+            WasmRetryPolicy::Infinite => RetryPolicy::Infinite,
+            WasmRetryPolicy::None => RetryPolicy::None,
+            WasmRetryPolicy::FixedDelay {
+                interval,
+                max_attempts,
+                max_duration,
+                on_max_attempts,
+            } => RetryPolicy::FixedDelay {
+                interval: interval.map(Duration::from_millis),
+                max_attempts,
+                max_duration: max_duration.map(Duration::from_millis),
+                on_max_attempts: on_max_attempts.into(),
+            },
+            WasmRetryPolicy::Exponential(config) => config.into(),
+        }
+    }
+}
+
+/// What to do once the retry policy runs out of attempts or duration.
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy, Default)]
+#[tsify(from_wasm_abi)]
+pub enum WasmOnMaxAttempts {
+    /// Complete the run with a terminal failure.
+    #[default]
+    FailAsTerminal,
+    /// Pause the invocation, until it's manually resumed. Requires service protocol V7 or newer.
+    Pause,
+}
+
+impl From<WasmOnMaxAttempts> for OnMaxAttempts {
+    fn from(value: WasmOnMaxAttempts) -> Self {
+        match value {
+            WasmOnMaxAttempts::FailAsTerminal => OnMaxAttempts::FailAsTerminal,
+            WasmOnMaxAttempts::Pause => OnMaxAttempts::Pause,
+        }
+    }
+}
+
+/// Result of a run, see [`WasmVM::propose_run_completion`].
+#[derive(Tsify, Serialize, Deserialize, Clone)]
+#[tsify(from_wasm_abi)]
+pub enum WasmRunResult {
+    Success(#[tsify(type = "Uint8Array")] serde_bytes::ByteBuf),
+    TerminalFailure(WasmFailure),
+    /// Retried according to the retry policy.
+    RetryableFailure {
+        error_message: String,
+        #[tsify(optional)]
+        #[serde(default)]
+        error_stacktrace: Option<String>,
+        /// Duration of the failed attempt, in millis.
+        attempt_duration: u64,
+        /// If true, the invocation is paused right away, regardless of the retry policy.
+        /// Requires service protocol V7 or newer.
+        #[tsify(optional)]
+        #[serde(default)]
+        should_pause: bool,
+    },
+}
+
+impl From<WasmRunResult> for RunExitResult {
+    fn from(value: WasmRunResult) -> Self {
+        match value {
+            WasmRunResult::Success(buffer) => RunExitResult::Success(buffer.into_vec().into()),
+            WasmRunResult::TerminalFailure(failure) => {
+                RunExitResult::TerminalFailure(failure.into())
+            }
+            WasmRunResult::RetryableFailure {
+                error_message,
+                error_stacktrace,
+                attempt_duration,
+                should_pause,
+            } => RunExitResult::RetryableFailure {
+                attempt_duration: Duration::from_millis(attempt_duration),
+                error: Error::internal(error_message)
+                    .with_stacktrace(error_stacktrace.unwrap_or_default())
+                    .with_should_pause(should_pause),
+            },
         }
     }
 }
 
 impl From<TerminalFailure> for WasmFailure {
     fn from(value: TerminalFailure) -> Self {
+        let (metadata, causes) = decode_causes(value.metadata);
         WasmFailure {
             code: value.code,
             message: value.message,
-            metadata: value
-                .metadata
-                .into_iter()
-                .map(|(k, v)| WasmFailureMetadata { key: k, value: v })
-                .collect(),
+            metadata,
+            causes,
+            kind: Some(WasmErrorKind::TerminalFailure),
+            stacktrace: None,
         }
     }
 }
 
 impl From<WasmFailure> for TerminalFailure {
     fn from(value: WasmFailure) -> Self {
+        let mut metadata: Vec<_> = value
+            .metadata
+            .into_iter()
+            .map(|metadata| (metadata.key, metadata.value))
+            .collect();
+        encode_causes(&mut metadata, value.causes);
         TerminalFailure {
             code: value.code,
             message: value.message,
-            metadata: value
-                .metadata
-                .into_iter()
-                .map(|metadata| (metadata.key, metadata.value))
-                .collect(),
+            metadata,
+        }
+    }
+}
+
+/// Options for [`WasmVM::notify_error`]. Only `message` is required.
+#[derive(Tsify, Serialize, Deserialize, Clone)]
+#[tsify(from_wasm_abi)]
+pub struct WasmNotifyErrorOptions {
+    /// Defaults to 500.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub code: Option<u16>,
+    pub message: String,
+    #[tsify(optional)]
+    #[serde(default)]
+    pub stacktrace: Option<String>,
+    /// The service protocol cannot transport metadata, so it's only logged together with the error.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub metadata: Vec<WasmFailureMetadata>,
+    /// Delay in milliseconds before the next retry, overriding the retry policy of the runtime.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub delay_override: Option<u64>,
+    /// If true, the runtime pauses the invocation instead of retrying it. Requires service protocol V7 or newer,
+    /// otherwise the error is dropped and the VM fails with an unsupported feature error.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub should_pause: bool,
+    #[tsify(optional)]
+    #[serde(default)]
+    pub related_command: Option<WasmCommandRelationship>,
+}
+
+/// Command an error passed to [`WasmVM::notify_error`] is related to.
+#[derive(Tsify, Serialize, Deserialize, Clone)]
+#[tsify(from_wasm_abi)]
+pub enum WasmCommandRelationship {
+    /// The last command added to the journal.
+    Last,
+    /// The command the SDK was about to add to the journal.
+    Next {
+        #[tsify(type = "WasmCommandType")]
+        #[serde(with = "command_type_discriminant")]
+        command_type: WasmCommandType,
+        #[tsify(optional)]
+        #[serde(default)]
+        command_name: Option<String>,
+    },
+    /// A command already in the journal, e.g. one whose completion failed.
+    Specific {
+        #[tsify(type = "WasmCommandType")]
+        #[serde(with = "command_type_discriminant")]
+        command_type: WasmCommandType,
+        command_index: u32,
+        #[tsify(optional)]
+        #[serde(default)]
+        command_name: Option<String>,
+    },
+}
+
+impl From<WasmCommandRelationship> for CommandRelationship {
+    fn from(value: WasmCommandRelationship) -> Self {
+        match value {
+            WasmCommandRelationship::Last => CommandRelationship::Last,
+            WasmCommandRelationship::Next {
+                command_type,
+                command_name,
+            } => CommandRelationship::Next {
+                ty: command_type.into(),
+                name: command_name.map(Into::into),
+            },
+            WasmCommandRelationship::Specific {
+                command_type,
+                command_index,
+                command_name,
+            } => CommandRelationship::Specific {
+                command_index,
+                ty: command_type.into(),
+                name: command_name.map(Into::into),
+            },
         }
     }
 }
@@ -465,7 +1104,7 @@ impl From<AwaitResponse> for WasmDoProgressResult {
     }
 }
 
-#[derive(Tsify, Serialize, Deserialize)]
+#[derive(Tsify, Serialize, Deserialize, Clone)]
 #[tsify(from_wasm_abi)]
 pub enum WasmUnresolvedFuture {
     Single(#[tsify(type = "number")] WasmNotificationHandle),
@@ -532,21 +1171,182 @@ impl From<SendHandle> for WasmSendHandle {
     }
 }
 
+/// Additional options for [`WasmVM::new`]. Every field can be omitted.
+#[derive(Tsify, Serialize, Deserialize, Default, Clone)]
+#[tsify(from_wasm_abi)]
+#[serde(default)]
+pub struct WasmVMOptions {
+    /// Format of the log records of the VM, defaults to `Text`.
+    #[tsify(optional)]
+    pub log_format: Option<LogFormat>,
+    /// If true, the log level of the VM can be overridden per invocation using the `x-restate-sdk-log-level` request header.
+    #[tsify(optional)]
+    pub allow_log_level_override: bool,
+    /// If set, log records are buffered and delivered in batches through `vm_log_batch`.
+    /// The buffer is flushed after `take_output` and `do_progress`, or when it contains this many records.
+    #[tsify(optional)]
+    pub log_batch_size: Option<u32>,
+    /// If true, span open, record and close events are forwarded to `vm_span_event`.
+    #[tsify(optional)]
+    pub export_spans: bool,
+    /// If true, the W3C `traceparent` and `tracestate` of the invocation input headers
+    /// are propagated to the headers of calls and sends, unless set explicitly.
+    ///
+    /// The headers of calls and sends are recorded in the journal and checked on replay,
+    /// so changing this option while invocations are in flight causes journal mismatches when they're retried.
+    #[tsify(optional)]
+    pub propagate_trace_context: bool,
+    /// If true, core log records emitted while replaying the journal are dropped.
+    /// Otherwise they're delivered, and can be told apart using the `state` of the log context.
+    #[tsify(optional)]
+    pub drop_replay_logs: bool,
+    /// If true, inputs, syscalls and outputs are recorded, see [`WasmVM::invocation_trace`].
+    #[tsify(optional)]
+    pub record_trace: bool,
+    /// Maximum size of the recorded events, measured on their JSON representation, defaults to 4 MiB.
+    /// Once reached, the recording stops and the trace is marked as truncated.
+    #[tsify(optional)]
+    pub record_trace_max_bytes: Option<u32>,
+    /// Children cancelled together with the invocation. Ignored when `explicit_cancellation` is true.
+    #[tsify(optional)]
+    pub implicit_cancellation: Option<WasmImplicitCancellationOptions>,
+    /// When the VM tells the runtime what the invocation is awaiting on, defaults to `DontSendWhenExecutingRun`.
+    #[tsify(optional)]
+    pub awaiting_on_policy: Option<WasmAwaitingOnPolicy>,
+}
+
+/// See [`WasmVMOptions::awaiting_on_policy`]. Requires service protocol V7 or newer, otherwise nothing is sent.
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy)]
+#[tsify(from_wasm_abi)]
+pub enum WasmAwaitingOnPolicy {
+    /// Send the awaited futures every time the invocation waits for external progress.
+    SendAlways,
+    /// Like `SendAlways`, except while a run is executing.
+    DontSendWhenExecutingRun,
+    /// Never send the awaited futures.
+    DontSend,
+}
+
+impl From<WasmAwaitingOnPolicy> for AwaitingOnPolicy {
+    fn from(value: WasmAwaitingOnPolicy) -> Self {
+        match value {
+            WasmAwaitingOnPolicy::SendAlways => AwaitingOnPolicy::SendAlways,
+            WasmAwaitingOnPolicy::DontSendWhenExecutingRun => {
+                AwaitingOnPolicy::DontSendWhenExecutingRun
+            }
+            WasmAwaitingOnPolicy::DontSend => AwaitingOnPolicy::DontSend,
+        }
+    }
+}
+
+/// See [`WasmVMOptions::implicit_cancellation`].
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy)]
+#[tsify(from_wasm_abi)]
+#[serde(default)]
+pub struct WasmImplicitCancellationOptions {
+    /// Cancel the calls, defaults to true.
+    #[tsify(optional)]
+    pub cancel_children_calls: bool,
+    /// Cancel the one way calls, defaults to false.
+    #[tsify(optional)]
+    pub cancel_children_one_way_calls: bool,
+}
+
+impl Default for WasmImplicitCancellationOptions {
+    fn default() -> Self {
+        Self {
+            cancel_children_calls: true,
+            cancel_children_one_way_calls: false,
+        }
+    }
+}
+
+/// Runtime statistics of a [`WasmVM`], see [`WasmVM::stats`].
+#[derive(Tsify, Serialize, Clone, Copy, Default)]
+#[tsify(into_wasm_abi)]
+pub struct WasmVMStats {
+    /// Bytes received through `notify_input`.
+    pub input_bytes: u64,
+    /// Bytes emitted through `take_output`.
+    pub output_bytes: u64,
+    /// Commands replayed from the journal.
+    pub replayed_commands: u32,
+    /// Commands newly added to the journal.
+    pub executed_commands: u32,
+    /// Notifications taken through `take_notification`.
+    pub taken_notifications: u32,
+    /// Runs the SDK was asked to execute by `do_progress`.
+    pub executed_runs: u32,
+    /// Total time spent in `do_progress`, in milliseconds.
+    pub do_progress_millis: f64,
+}
+
+impl WasmVMStats {
+    fn record_commands(&mut self, previous_command_index: i64, replaying: bool, vm: &CoreVM) {
+        let new_commands = (vm.last_command_index() - previous_command_index).max(0) as u32;
+        if replaying {
+            self.replayed_commands += new_commands;
+        } else {
+            self.executed_commands += new_commands;
+        }
+    }
+}
+
 // VM implementation
 
 #[wasm_bindgen]
 pub struct WasmVM {
     vm: CoreVM,
+    logger_id: u32,
     log_dispatcher: Dispatch,
+    log_context: SharedLogContext,
+    log_writer: MakeWebConsoleWriter,
+    log_reloader: LogReloader,
+    log_buffer: Option<SharedLogBuffer>,
+    propagate_trace_context: bool,
+    trace_context: Option<TraceContext>,
+    stats: Cell<WasmVMStats>,
+    poisoned: Cell<bool>,
+    trace: Option<InvocationTrace>,
+    clock_override: Option<Duration>,
 }
 
 macro_rules! use_log_dispatcher {
     ($vm:expr, $f:expr) => {{
-        let WasmVM { vm, log_dispatcher } = $vm;
-        tracing::dispatcher::with_default(&log_dispatcher, || $f(vm))
+        let WasmVM {
+            vm,
+            logger_id,
+            log_dispatcher,
+            log_context,
+            log_writer,
+            stats,
+            poisoned,
+            ..
+        } = $vm;
+        panic_isolation::isolate(poisoned, || {
+            if let Ok(mut log_context) = log_context.lock() {
+                log_context.refresh(vm);
+            }
+            let previous_command_index = vm.last_command_index();
+            let replaying = vm.state().is_replaying();
+            let _active_vm_guard = ActiveVMGuard::enter(*logger_id, log_writer.clone());
+            let _pending_logs_guard = log_writer.begin_call();
+
+            let res = tracing::dispatcher::with_default(&log_dispatcher, || $f(vm));
+            if let Ok(mut log_context) = log_context.lock() {
+                log_context.refresh(vm);
+            }
+
+            let mut new_stats = stats.get();
+            new_stats.record_commands(previous_command_index, replaying, vm);
+            stats.set(new_stats);
+            res
+        })
     }};
 }
 
+// Failures are converted to JS values right away, their size doesn't matter
+#[allow(clippy::result_large_err)]
 #[wasm_bindgen]
 impl WasmVM {
     #[wasm_bindgen(constructor)]
@@ -557,12 +1357,61 @@ impl WasmVM {
         disable_payload_checks: bool,
         explicit_cancellation: bool,
         on_journal_mismatch: WasmJournalMismatchBehavior,
+        options: Option<WasmVMOptions>,
     ) -> Result<WasmVM, WasmFailure> {
-        let log_dispatcher = Dispatch::new(log_subscriber(log_level, Some(logger_id)));
+        let options = options.unwrap_or_default();
+        let trace = options.record_trace.then(|| {
+            InvocationTrace::new(
+                &headers,
+                disable_payload_checks,
+                explicit_cancellation,
+                on_journal_mismatch,
+                &options,
+            )
+        });
+        let headers = WasmHeaderList::from(headers);
+        let invocation_id = headers
+            .extract(INVOCATION_ID_HEADER)
+            .ok()
+            .flatten()
+            .map(ToOwned::to_owned);
+        let log_level = if options.allow_log_level_override {
+            headers
+                .extract(LOG_LEVEL_OVERRIDE_HEADER)
+                .ok()
+                .flatten()
+                .and_then(LogLevel::parse)
+                .unwrap_or(log_level)
+        } else {
+            log_level
+        };
+        let log_context = Arc::new(Mutex::new(WasmLogContext::new(invocation_id)));
+        let log_buffer = options.log_batch_size.map(|log_batch_size| {
+            Arc::new(Mutex::new(LogBuffer::new(
+                Some(logger_id),
+                cmp::max(log_batch_size, 1) as usize,
+            )))
+        });
+        let log_writer = MakeWebConsoleWriter {
+            context: Some(log_context.clone()),
+            buffer: log_buffer.clone(),
+            pending: Some(Arc::default()),
+            drop_replay_logs: options.drop_replay_logs,
+            ..MakeWebConsoleWriter::new(Some(logger_id))
+        };
+        let (log_subscriber, log_reloader) = log_subscriber(
+            log_level,
+            options.log_format.unwrap_or_default(),
+            log_writer.clone(),
+            options
+                .export_spans
+                .then(|| SpanExportLayer::new(Some(logger_id))),
+        );
+        let log_dispatcher = Dispatch::new(log_subscriber);
 
         let vm = tracing::dispatcher::with_default(&log_dispatcher, || {
             CoreVM::new(
-                WasmHeaderList::from(headers),
+                headers,
                 VMOptions {
                     non_determinism_checks: if disable_payload_checks {
                         NonDeterministicChecksOption::PayloadChecksDisabled
@@ -572,49 +1421,87 @@ impl WasmVM {
                     implicit_cancellation: if explicit_cancellation {
                         ImplicitCancellationOption::Disabled
                     } else {
+                        let implicit_cancellation =
+                            options.implicit_cancellation.unwrap_or_default();
                         ImplicitCancellationOption::Enabled {
-                            cancel_children_calls: true,
-                            cancel_children_one_way_calls: false,
+                            cancel_children_calls: implicit_cancellation.cancel_children_calls,
+                            cancel_children_one_way_calls: implicit_cancellation
+                                .cancel_children_one_way_calls,
                         }
                     },
-                    awaiting_on_policy: Default::default(),
+                    awaiting_on_policy: options
+                        .awaiting_on_policy
+                        .map(Into::into)
+                        .unwrap_or_default(),
                     journal_mismatch_retry_behavior: on_journal_mismatch.into(),
                 },
             )
         })?;
 
-        Ok(Self { vm, log_dispatcher })
+        Ok(Self {
+            vm,
+            logger_id,
+            log_dispatcher,
+            log_context,
+            log_writer,
+            log_reloader,
+            log_buffer,
+            propagate_trace_context: options.propagate_trace_context,
+            trace_context: None,
+            stats: Cell::default(),
+            poisoned: Cell::new(false),
+            trace,
+            clock_override: None,
+        })
+    }
+
+    /// Changes the log level of this VM, without affecting the overall log subscriber.
+    pub fn set_log_level(&mut self, level: LogLevel) {
+        self.log_reloader.set_level(level);
     }
 
     pub fn get_response_head(&self) -> WasmResponseHead {
         use_log_dispatcher!(self, |vm| CoreVM::get_response_head(vm).into())
     }
 
-    pub fn notify_input(&mut self, buffer: Vec<u8>) {
+    pub fn notify_input(&mut self, buffer: Vec<u8>) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::NotifyInput {
+            buffer: buffer.clone(),
+        });
+        self.update_stats(|stats| stats.input_bytes += buffer.len() as u64);
         let buf = buffer.into();
-        use_log_dispatcher!(self, |vm| CoreVM::notify_input(vm, buf))
+        use_log_dispatcher!(self, |vm| {
+            CoreVM::notify_input(vm, buf);
+            Ok(())
+        })
+        .map_err(Into::into)
     }
 
-    pub fn notify_input_closed(&mut self) {
-        self.vm.notify_input_closed();
+    pub fn notify_input_closed(&mut self) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::NotifyInputClosed);
+        use_log_dispatcher!(self, |vm| {
+            CoreVM::notify_input_closed(vm);
+            Ok(())
+        })
+        .map_err(Into::into)
     }
 
-    pub fn notify_error(&mut self, error_message: String, stacktrace: Option<String>) {
-        let mut e = Error::internal(error_message);
-        if let Some(stacktrace) = stacktrace {
-            e = e.with_stacktrace(stacktrace);
-        }
-
-        use_log_dispatcher!(self, |vm| CoreVM::notify_error(vm, e, None))
-    }
-
-    pub fn notify_error_with_delay_override(
-        &mut self,
-        error_message: String,
-        stacktrace: Option<String>,
-        delay_override: Option<u64>,
-    ) {
-        let mut e = Error::internal(error_message);
+    pub fn notify_error(&mut self, options: WasmNotifyErrorOptions) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::NotifyError {
+            options: options.clone(),
+        });
+        let WasmNotifyErrorOptions {
+            code,
+            message,
+            stacktrace,
+            metadata,
+            delay_override,
+            should_pause,
+            related_command,
+        } = options;
+
+        let mut e = Error::new(code.unwrap_or(codes::INTERNAL.code()), message)
+            .with_should_pause(should_pause);
         if let Some(stacktrace) = stacktrace {
             e = e.with_stacktrace(stacktrace);
         }
@@ -622,130 +1509,151 @@ impl WasmVM {
             e = e.with_next_retry_delay_override(Duration::from_millis(delay_override))
         }
 
-        use_log_dispatcher!(self, |vm| CoreVM::notify_error(vm, e, None))
-    }
-
-    pub fn notify_error_for_next_command(
-        &mut self,
-        error_message: String,
-        stacktrace: Option<String>,
-        wasm_command_type: WasmCommandType,
-    ) {
-        let mut e = Error::internal(error_message);
-        if let Some(stacktrace) = stacktrace {
-            e = e.with_stacktrace(stacktrace);
-        }
-
-        use_log_dispatcher!(self, |vm| CoreVM::notify_error(
-            vm,
-            e,
-            Some(CommandRelationship::Next {
-                ty: wasm_command_type.into(),
-                name: None,
-            })
-        ))
+        use_log_dispatcher!(self, |vm| {
+            if !metadata.is_empty() {
+                let metadata = metadata
+                    .iter()
+                    .map(|m| format!("{}={}", m.key, m.value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                tracing::warn!("Error metadata: {metadata}");
+            }
+            CoreVM::notify_error(vm, e, related_command.map(Into::into));
+            Ok(())
+        })
+        .map_err(Into::into)
     }
 
-    pub fn notify_error_for_specific_command(
+    /// Pauses the invocation after an error, instead of retrying it, so it can be resumed by an operator.
+    /// Requires service protocol V7 or newer.
+    pub fn notify_pause(
         &mut self,
-        error_message: String,
+        reason: String,
         stacktrace: Option<String>,
-        wasm_command_type: WasmCommandType,
-        command_index: u32,
-        command_name: Option<String>,
-    ) {
-        let mut e = Error::internal(error_message);
-        if let Some(stacktrace) = stacktrace {
-            e = e.with_stacktrace(stacktrace);
-        }
-
-        use_log_dispatcher!(self, |vm| CoreVM::notify_error(
-            vm,
-            e,
-            Some(CommandRelationship::Specific {
-                command_index,
-                ty: wasm_command_type.into(),
-                name: command_name.map(Into::into)
-            })
-        ))
+    ) -> Result<(), WasmFailure> {
+        self.notify_error(WasmNotifyErrorOptions {
+            code: None,
+            message: reason,
+            stacktrace,
+            metadata: vec![],
+            delay_override: None,
+            should_pause: true,
+            related_command: None,
+        })
     }
 
-    pub fn take_output(&mut self) -> Uint8Array {
-        (&*use_log_dispatcher!(self, CoreVM::take_output)).into()
+    pub fn take_output(&mut self) -> Result<Uint8Array, WasmFailure> {
+        Ok((&*self.take_output_bytes()?).into())
     }
 
     pub fn is_ready_to_execute(&self) -> Result<bool, WasmFailure> {
         use_log_dispatcher!(self, CoreVM::is_ready_to_execute).map_err(Into::into)
     }
 
-    pub fn is_completed(&self, handle: WasmNotificationHandle) -> bool {
-        use_log_dispatcher!(self, |vm| CoreVM::is_completed(vm, handle.into()))
+    pub fn is_completed(&self, handle: WasmNotificationHandle) -> Result<bool, WasmFailure> {
+        use_log_dispatcher!(self, |vm| Ok(CoreVM::is_completed(vm, handle.into())))
+            .map_err(Into::into)
     }
 
     pub fn do_progress(
         &mut self,
         future: WasmUnresolvedFuture,
     ) -> Result<WasmDoProgressResult, WasmFailure> {
-        Ok(use_log_dispatcher!(self, |vm| CoreVM::do_await(vm, future.into()))?.into())
+        self.record(|| TraceEvent::DoProgress {
+            future: future.clone(),
+        });
+        let start = performance_now();
+        let result = use_log_dispatcher!(self, |vm| CoreVM::do_await(vm, future.into()));
+        self.update_stats(|stats| {
+            stats.do_progress_millis += performance_now() - start;
+            if let Ok(AwaitResponse::ExecuteRun(_)) = result {
+                stats.executed_runs += 1;
+            }
+        });
+        self.flush_logs();
+        Ok(result?.into())
     }
 
     pub fn take_notification(
         &mut self,
         handle: WasmNotificationHandle,
     ) -> Result<WasmAsyncResultValue, WasmFailure> {
-        Ok(
-            match use_log_dispatcher!(self, |vm| CoreVM::take_notification(vm, handle.into()))? {
-                None => WasmAsyncResultValue::NotReady,
-                Some(Value::Void) => WasmAsyncResultValue::Empty,
-                Some(Value::Success(b)) => WasmAsyncResultValue::Success(b.to_vec().into()),
-                Some(Value::Failure(f)) => WasmAsyncResultValue::Failure(f.into()),
-                Some(Value::StateKeys(keys)) => WasmAsyncResultValue::StateKeys(keys),
-                Some(Value::InvocationId(invocation_id)) => {
-                    WasmAsyncResultValue::InvocationId(invocation_id)
-                }
-            },
-        )
+        self.record(|| TraceEvent::TakeNotification { handle });
+        let notification =
+            use_log_dispatcher!(self, |vm| CoreVM::take_notification(vm, handle.into()))?;
+        if notification.is_some() {
+            self.update_stats(|stats| stats.taken_notifications += 1);
+        }
+        Ok(match notification {
+            None => WasmAsyncResultValue::NotReady,
+            Some(Value::Void) => WasmAsyncResultValue::Empty,
+            Some(Value::Success(b)) => WasmAsyncResultValue::Success(b.to_vec().into()),
+            Some(Value::Failure(f)) => WasmAsyncResultValue::Failure(f.into()),
+            Some(Value::StateKeys(keys)) => WasmAsyncResultValue::StateKeys(keys),
+            Some(Value::InvocationId(invocation_id)) => {
+                WasmAsyncResultValue::InvocationId(invocation_id)
+            }
+        })
     }
 
     // Syscall(s)
 
     pub fn sys_input(&mut self) -> Result<WasmInput, WasmFailure> {
-        use_log_dispatcher!(self, CoreVM::sys_input)
-            .map(Into::into)
-            .map_err(Into::into)
+        Ok(self.sys_input_raw()?.into())
     }
 
-    pub fn sys_get_state(&mut self, key: String) -> Result<WasmNotificationHandle, WasmFailure> {
+    pub fn sys_get_state(
+        &mut self,
+        key: String,
+        options: Option<WasmPayloadOptions>,
+    ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysGetState {
+            key: key.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_state_get(
             vm,
             key,
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map(Into::into)
         .map_err(Into::into)
     }
 
     pub fn sys_get_state_keys(&mut self) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysGetStateKeys);
         use_log_dispatcher!(self, CoreVM::sys_state_get_keys)
             .map(Into::into)
             .map_err(Into::into)
     }
 
-    pub fn sys_set_state(&mut self, key: String, buffer: Vec<u8>) -> Result<(), WasmFailure> {
+    pub fn sys_set_state(
+        &mut self,
+        key: String,
+        buffer: Vec<u8>,
+        options: Option<WasmPayloadOptions>,
+    ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysSetState {
+            key: key.clone(),
+            buffer: buffer.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_state_set(
             vm,
             key,
             buffer.into(),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map_err(Into::into)
     }
 
     pub fn sys_clear_state(&mut self, key: String) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysClearState { key: key.clone() });
         use_log_dispatcher!(self, |vm| CoreVM::sys_state_clear(vm, key)).map_err(Into::into)
     }
 
     pub fn sys_clear_all_state(&mut self) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysClearAllState);
         use_log_dispatcher!(self, CoreVM::sys_state_clear_all).map_err(Into::into)
     }
 
@@ -754,7 +1662,12 @@ impl WasmVM {
         millis: u64,
         name: Option<String>,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
-        let now = now_since_unix_epoch();
+        let now = self.now();
+        self.record(|| TraceEvent::SysSleep {
+            millis,
+            name: name.clone(),
+            now: trace_now(now),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_sleep(
             vm,
             name.unwrap_or_default(),
@@ -769,6 +1682,9 @@ impl WasmVM {
         &mut self,
         invocation_id: String,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysAttachInvocation {
+            invocation_id: invocation_id.clone(),
+        });
         let target = restate_sdk_shared_core::AttachInvocationTarget::InvocationId(invocation_id);
 
         use_log_dispatcher!(self, |vm| CoreVM::sys_attach_invocation(vm, target))
@@ -780,6 +1696,9 @@ impl WasmVM {
         &mut self,
         invocation_id: String,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysGetInvocationOutput {
+            invocation_id: invocation_id.clone(),
+        });
         let target = restate_sdk_shared_core::AttachInvocationTarget::InvocationId(invocation_id);
 
         use_log_dispatcher!(self, |vm| CoreVM::sys_get_invocation_output(vm, target))
@@ -787,6 +1706,7 @@ impl WasmVM {
             .map_err(Into::into)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn sys_call(
         &mut self,
         service: String,
@@ -798,22 +1718,39 @@ impl WasmVM {
         scope: Option<String>,
         limit_key: Option<String>,
         name: Option<String>,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<WasmCallHandle, WasmFailure> {
-        use_log_dispatcher!(self, |vm| CoreVM::sys_call(
-            vm,
-            Target {
-                service,
-                handler,
-                key,
-                idempotency_key,
-                scope,
-                limit_key,
-                headers: headers.into_iter().map(Header::from).collect(),
-            },
-            buffer.to_vec().into(),
-            name,
-            Default::default(),
-        ))
+        self.record(|| TraceEvent::SysCall {
+            service: service.clone(),
+            handler: handler.clone(),
+            buffer: buffer.clone(),
+            key: key.clone(),
+            headers: trace_headers(&headers),
+            idempotency_key: idempotency_key.clone(),
+            scope: scope.clone(),
+            limit_key: limit_key.clone(),
+            name: name.clone(),
+            options,
+        });
+        let trace_context = &self.trace_context;
+        use_log_dispatcher!(self, |vm: &mut CoreVM| {
+            let headers = outgoing_headers(trace_context.as_ref(), headers);
+            CoreVM::sys_call(
+                vm,
+                Target {
+                    service,
+                    handler,
+                    key,
+                    idempotency_key,
+                    scope,
+                    limit_key,
+                    headers,
+                },
+                buffer.to_vec().into(),
+                name,
+                options.map(Into::into).unwrap_or_default(),
+            )
+        })
         .map(Into::into)
         .map_err(Into::into)
     }
@@ -831,28 +1768,49 @@ impl WasmVM {
         scope: Option<String>,
         limit_key: Option<String>,
         name: Option<String>,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<WasmSendHandle, WasmFailure> {
-        use_log_dispatcher!(self, |vm| CoreVM::sys_send(
-            vm,
-            Target {
-                service,
-                handler,
-                key,
-                idempotency_key,
-                scope,
-                limit_key,
-                headers: headers.into_iter().map(Header::from).collect(),
-            },
-            buffer.to_vec().into(),
-            delay.map(|delay| now_since_unix_epoch() + Duration::from_millis(delay)),
-            name,
-            Default::default()
-        ))
+        let now = self.now();
+        self.record(|| TraceEvent::SysSend {
+            service: service.clone(),
+            handler: handler.clone(),
+            buffer: buffer.clone(),
+            key: key.clone(),
+            headers: trace_headers(&headers),
+            delay,
+            idempotency_key: idempotency_key.clone(),
+            scope: scope.clone(),
+            limit_key: limit_key.clone(),
+            name: name.clone(),
+            now: trace_now(now),
+            options,
+        });
+        let trace_context = &self.trace_context;
+        use_log_dispatcher!(self, |vm: &mut CoreVM| {
+            let headers = outgoing_headers(trace_context.as_ref(), headers);
+            CoreVM::sys_send(
+                vm,
+                Target {
+                    service,
+                    handler,
+                    key,
+                    idempotency_key,
+                    scope,
+                    limit_key,
+                    headers,
+                },
+                buffer.to_vec().into(),
+                delay.map(|delay| now + Duration::from_millis(delay)),
+                name,
+                options.map(Into::into).unwrap_or_default(),
+            )
+        })
         .map(Into::into)
         .map_err(Into::into)
     }
 
     pub fn sys_awakeable(&mut self) -> Result<WasmAwakeable, WasmFailure> {
+        self.record(|| TraceEvent::SysAwakeable);
         use_log_dispatcher!(self, CoreVM::sys_awakeable)
             .map(
                 |AwakeableHandle { id, handle }: AwakeableHandle| WasmAwakeable {
@@ -867,12 +1825,18 @@ impl WasmVM {
         &mut self,
         id: String,
         buffer: Vec<u8>,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysCompleteAwakeableSuccess {
+            id: id.clone(),
+            buffer: buffer.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_awakeable(
             vm,
             id,
             NonEmptyValue::Success(buffer.to_vec().into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map_err(Into::into)
     }
@@ -881,12 +1845,18 @@ impl WasmVM {
         &mut self,
         id: String,
         value: WasmFailure,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysCompleteAwakeableFailure {
+            id: id.clone(),
+            value: value.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_awakeable(
             vm,
             id,
             NonEmptyValue::Failure(value.into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map_err(Into::into)
     }
@@ -895,6 +1865,9 @@ impl WasmVM {
         &mut self,
         signal_name: String,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysSignal {
+            signal_name: signal_name.clone(),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::create_signal_handle(vm, signal_name))
             .map(Into::into)
             .map_err(Into::into)
@@ -906,6 +1879,11 @@ impl WasmVM {
         signal_name: String,
         buffer: Vec<u8>,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysCompleteSignalSuccess {
+            invocation_id: invocation_id.clone(),
+            signal_name: signal_name.clone(),
+            buffer: buffer.clone(),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_signal(
             vm,
             invocation_id,
@@ -921,6 +1899,11 @@ impl WasmVM {
         signal_name: String,
         value: WasmFailure,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysCompleteSignalFailure {
+            invocation_id: invocation_id.clone(),
+            signal_name: signal_name.clone(),
+            value: value.clone(),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_signal(
             vm,
             invocation_id,
@@ -931,12 +1914,14 @@ impl WasmVM {
     }
 
     pub fn sys_get_promise(&mut self, key: String) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysGetPromise { key: key.clone() });
         use_log_dispatcher!(self, |vm| CoreVM::sys_get_promise(vm, key))
             .map(Into::into)
             .map_err(Into::into)
     }
 
     pub fn sys_peek_promise(&mut self, key: String) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysPeekPromise { key: key.clone() });
         use_log_dispatcher!(self, |vm| CoreVM::sys_peek_promise(vm, key))
             .map(Into::into)
             .map_err(Into::into)
@@ -946,12 +1931,18 @@ impl WasmVM {
         &mut self,
         key: String,
         buffer: Vec<u8>,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysCompletePromiseSuccess {
+            key: key.clone(),
+            buffer: buffer.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_promise(
             vm,
             key,
             NonEmptyValue::Success(buffer.to_vec().into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map(Into::into)
         .map_err(Into::into)
@@ -961,18 +1952,25 @@ impl WasmVM {
         &mut self,
         key: String,
         value: WasmFailure,
+        options: Option<WasmPayloadOptions>,
     ) -> Result<WasmNotificationHandle, WasmFailure> {
+        self.record(|| TraceEvent::SysCompletePromiseFailure {
+            key: key.clone(),
+            value: value.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_complete_promise(
             vm,
             key,
             NonEmptyValue::Failure(value.into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
         .map(Into::into)
         .map_err(Into::into)
     }
 
     pub fn sys_run(&mut self, name: String) -> Result<WasmRun, WasmFailure> {
+        self.record(|| TraceEvent::SysRun { name: name.clone() });
         use_log_dispatcher!(self, |vm| CoreVM::sys_run(vm, name))
             .map(|RunHandle { replayed, handle }: RunHandle| WasmRun {
                 replayed,
@@ -981,110 +1979,26 @@ impl WasmVM {
             .map_err(Into::into)
     }
 
-    pub fn propose_run_completion_success(
+    /// Completes the run with the given result. Retryable failures are retried following `retry_policy`,
+    /// defaulting to [`WasmRetryPolicy::Infinite`]. The retry policy is ignored for other results.
+    pub fn propose_run_completion(
         &mut self,
         handle: WasmNotificationHandle,
-        buffer: Vec<u8>,
+        result: WasmRunResult,
+        retry_policy: Option<WasmRetryPolicy>,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::ProposeRunCompletion {
+            handle,
+            result: result.clone(),
+            retry_policy: retry_policy.clone(),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::propose_run_completion(
             vm,
             handle.into(),
-            RunExitResult::Success(buffer.to_vec().into()),
-            RetryPolicy::None,
-        ))
-        .map_err(Into::into)
-    }
-
-    pub fn propose_run_completion_failure(
-        &mut self,
-        handle: WasmNotificationHandle,
-        value: WasmFailure,
-    ) -> Result<(), WasmFailure> {
-        use_log_dispatcher!(self, |vm| CoreVM::propose_run_completion(
-            vm,
-            handle.into(),
-            RunExitResult::TerminalFailure(value.into()),
-            RetryPolicy::None
-        ))
-        .map_err(Into::into)
-    }
-
-    pub fn propose_run_completion_failure_transient(
-        &mut self,
-        handle: WasmNotificationHandle,
-        error_message: String,
-        error_stacktrace: Option<String>,
-        attempt_duration: u64,
-        config: Option<WasmExponentialRetryConfig>,
-    ) -> Result<(), WasmFailure> {
-        use_log_dispatcher!(self, |vm| CoreVM::propose_run_completion(
-            vm,
-            handle.into(),
-            RunExitResult::RetryableFailure {
-                attempt_duration: Duration::from_millis(attempt_duration),
-                error: Error::internal(error_message)
-                    .with_stacktrace(error_stacktrace.unwrap_or_default()),
-            },
-            config
-                .map(|config| config.into())
-                .unwrap_or(RetryPolicy::Infinite)
-        ))
-        .map_err(Into::into)
-    }
-
-    pub fn propose_run_completion_failure_transient_with_delay_override(
-        &mut self,
-        handle: WasmNotificationHandle,
-        error_message: String,
-        error_stacktrace: Option<String>,
-        attempt_duration: u64,
-        delay_override: Option<u64>,
-        max_retry_attempts_override: Option<u32>,
-        max_retry_duration_override: Option<u64>,
-    ) -> Result<(), WasmFailure> {
-        let retry_policy = if delay_override.is_some()
-            || max_retry_attempts_override.is_some()
-            || max_retry_duration_override.is_some()
-        {
-            RetryPolicy::FixedDelay {
-                interval: delay_override.map(Duration::from_millis),
-                max_attempts: max_retry_attempts_override,
-                max_duration: max_retry_duration_override.map(Duration::from_millis),
-                on_max_attempts: OnMaxAttempts::FailAsTerminal,
-            }
-        } else {
-            RetryPolicy::Infinite
-        };
-        use_log_dispatcher!(self, |vm| CoreVM::propose_run_completion(
-            vm,
-            handle.into(),
-            RunExitResult::RetryableFailure {
-                attempt_duration: Duration::from_millis(attempt_duration),
-                error: Error::internal(error_message)
-                    .with_stacktrace(error_stacktrace.unwrap_or_default()),
-            },
+            result.into(),
             retry_policy
-        ))
-        .map_err(Into::into)
-    }
-
-    pub fn propose_run_completion_failure_transient_with_pause(
-        &mut self,
-        handle: WasmNotificationHandle,
-        error_message: String,
-        error_stacktrace: Option<String>,
-        attempt_duration: u64,
-    ) -> Result<(), WasmFailure> {
-        use_log_dispatcher!(self, |vm| CoreVM::propose_run_completion(
-            vm,
-            handle.into(),
-            RunExitResult::RetryableFailure {
-                attempt_duration: Duration::from_millis(attempt_duration),
-                error: Error::internal(error_message)
-                    .with_stacktrace(error_stacktrace.unwrap_or_default())
-                    .with_should_pause(true),
-            },
-            RetryPolicy::Infinite
+                .map(Into::into)
+                .unwrap_or(RetryPolicy::Infinite)
         ))
         .map_err(Into::into)
     }
@@ -1093,53 +2007,156 @@ impl WasmVM {
         &mut self,
         target_invocation_id: String,
     ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysCancelInvocation {
+            target_invocation_id: target_invocation_id.clone(),
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_cancel_invocation(
             vm,
             target_invocation_id
         ))
-        .map(Into::into)
         .map_err(Into::into)
     }
 
-    pub fn sys_write_output_success(&mut self, buffer: Vec<u8>) -> Result<(), WasmFailure> {
+    pub fn sys_write_output_success(
+        &mut self,
+        buffer: Vec<u8>,
+        options: Option<WasmPayloadOptions>,
+    ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysWriteOutputSuccess {
+            buffer: buffer.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_write_output(
             vm,
             NonEmptyValue::Success(buffer.to_vec().into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
-        .map(Into::into)
         .map_err(Into::into)
     }
 
-    pub fn sys_write_output_failure(&mut self, value: WasmFailure) -> Result<(), WasmFailure> {
+    pub fn sys_write_output_failure(
+        &mut self,
+        value: WasmFailure,
+        options: Option<WasmPayloadOptions>,
+    ) -> Result<(), WasmFailure> {
+        self.record(|| TraceEvent::SysWriteOutputFailure {
+            value: value.clone(),
+            options,
+        });
         use_log_dispatcher!(self, |vm| CoreVM::sys_write_output(
             vm,
             NonEmptyValue::Failure(value.into()),
-            Default::default()
+            options.map(Into::into).unwrap_or_default()
         ))
-        .map(Into::into)
         .map_err(Into::into)
     }
 
     pub fn sys_end(&mut self) -> Result<(), WasmFailure> {
-        use_log_dispatcher!(self, CoreVM::sys_end)
-            .map(Into::into)
-            .map_err(Into::into)
+        self.record(|| TraceEvent::SysEnd);
+        use_log_dispatcher!(self, CoreVM::sys_end).map_err(Into::into)
     }
 
     pub fn is_processing(&self) -> bool {
         use_log_dispatcher!(self, |vm| CoreVM::state(vm).is_processing())
     }
 
-    pub fn last_command_index(&self) -> i32 {
-        use_log_dispatcher!(self, |vm| CoreVM::last_command_index(vm) as i32)
+    pub fn last_command_index(&self) -> Result<i32, WasmFailure> {
+        use_log_dispatcher!(self, |vm| Ok(CoreVM::last_command_index(vm) as i32))
+            .map_err(Into::into)
+    }
+
+    pub fn stats(&self) -> WasmVMStats {
+        self.stats.get()
+    }
+
+    /// Returns the trace recorded so far as a JSON string, or `None` if [`WasmVMOptions::record_trace`] is disabled.
+    ///
+    /// The trace can be replayed with `replay_invocation_trace`.
+    pub fn invocation_trace(&self) -> Result<Option<String>, WasmFailure> {
+        self.trace
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| Error::internal(format!("Cannot serialize the trace: {e}")).into())
+    }
+}
+
+impl WasmVM {
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn sys_input_raw(&mut self) -> Result<Input, WasmFailure> {
+        self.record(|| TraceEvent::SysInput);
+        let input = use_log_dispatcher!(self, CoreVM::sys_input)?;
+        if let Ok(mut log_context) = self.log_context.lock() {
+            log_context.invocation_id = Some(input.invocation_id.clone());
+        }
+        if self.propagate_trace_context {
+            self.trace_context = TraceContext::from_headers(&input.headers);
+        }
+        Ok(input)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn take_output_bytes(&mut self) -> Result<Bytes, WasmFailure> {
+        let output = use_log_dispatcher!(self, |vm| Ok(CoreVM::take_output(vm)))?;
+        self.update_stats(|stats| stats.output_bytes += output.len() as u64);
+        self.record(|| TraceEvent::TakeOutput {
+            output: output.to_vec(),
+        });
+        self.flush_logs();
+        Ok(output)
+    }
+
+    fn record(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(event());
+        }
+    }
+
+    /// Current time, unless overridden when replaying a trace.
+    fn now(&mut self) -> Duration {
+        self.clock_override
+            .take()
+            .unwrap_or_else(now_since_unix_epoch)
+    }
+
+    pub(crate) fn set_clock_override(&mut self, now: Duration) {
+        self.clock_override = Some(now);
+    }
+
+    fn update_stats(&self, f: impl FnOnce(&mut WasmVMStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    fn flush_logs(&self) {
+        if let Some(log_buffer) = &self.log_buffer {
+            flush_log_records(log_buffer);
+        }
+    }
+}
+
+impl Drop for WasmVM {
+    fn drop(&mut self) {
+        self.flush_logs();
+    }
+}
+
+fn outgoing_headers(trace_context: Option<&TraceContext>, headers: Vec<WasmHeader>) -> Vec<Header> {
+    let mut headers: Vec<Header> = headers.into_iter().map(Header::from).collect();
+    if let Some(trace_context) = trace_context {
+        trace_context.inject(&mut headers);
     }
+    headers
 }
 
 fn now_since_unix_epoch() -> Duration {
     Duration::from_millis(js_sys::Date::now() as u64)
 }
 
+const INVOCATION_ID_HEADER: &str = "x-restate-invocation-id";
+const LOG_LEVEL_OVERRIDE_HEADER: &str = "x-restate-sdk-log-level";
+
 // We need this wrapper for the shared core
 struct WasmHeaderList(Vec<WasmHeader>);
 
@@ -1188,3 +2205,123 @@ impl WasmIdentityVerifier {
 pub fn cancel_handle() -> WasmNotificationHandle {
     CANCEL_NOTIFICATION_HANDLE.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_writer(
+        drop_replay_logs: bool,
+    ) -> (MakeWebConsoleWriter, SharedLogContext, SharedLogBuffer) {
+        let context = Arc::new(Mutex::new(WasmLogContext::new(Some("inv".to_owned()))));
+        let buffer = Arc::new(Mutex::new(LogBuffer::new(None, 100)));
+        let writer = MakeWebConsoleWriter {
+            context: Some(context.clone()),
+            buffer: Some(buffer.clone()),
+            pending: Some(Arc::default()),
+            drop_replay_logs,
+            ..MakeWebConsoleWriter::new(Some(1))
+        };
+        (writer, context, buffer)
+    }
+
+    fn set_state(context: &SharedLogContext, state: State, command_index: i32) {
+        let mut context = context.lock().unwrap();
+        context.state = state_name(state);
+        context.command_index = command_index;
+    }
+
+    fn delivered(buffer: &SharedLogBuffer) -> Vec<(String, Option<&'static str>, Option<i32>)> {
+        buffer
+            .lock()
+            .unwrap()
+            .records
+            .iter()
+            .map(|r| {
+                (
+                    String::from_utf8(r.message.to_vec()).unwrap(),
+                    r.context.as_ref().map(|c| c.state),
+                    r.context.as_ref().map(|c| c.command_index),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn records_emitted_during_a_call_get_the_context_after_the_call() {
+        let (writer, context, buffer) = test_writer(false);
+        set_state(&context, State::Replaying, 0);
+
+        let guard = writer.begin_call();
+        writer.write_record(LogLevel::DEBUG, b"during the call");
+        assert!(delivered(&buffer).is_empty());
+        set_state(&context, State::Processing, 1);
+        drop(guard);
+
+        writer.write_record(LogLevel::DEBUG, b"after the call");
+
+        assert_eq!(
+            delivered(&buffer),
+            [
+                ("during the call".to_owned(), Some("Processing"), Some(1)),
+                ("after the call".to_owned(), Some("Processing"), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_of_an_interrupted_call_get_the_context_before_the_call() {
+        let (writer, context, buffer) = test_writer(false);
+        set_state(&context, State::Processing, 1);
+
+        let _guard = writer.begin_call();
+        writer.write_record(LogLevel::ERROR, b"before the panic");
+        // What the panic hook does, as the guard is never dropped with panic = "abort"
+        writer.deliver_pending();
+
+        assert_eq!(
+            delivered(&buffer),
+            [("before the panic".to_owned(), Some("Processing"), Some(1))]
+        );
+    }
+
+    #[test]
+    fn replay_records_are_dropped_using_the_context_after_the_call() {
+        let (writer, context, buffer) = test_writer(true);
+        set_state(&context, State::Replaying, 0);
+
+        writer.write_record(LogLevel::DEBUG, b"replaying");
+        {
+            let _guard = writer.begin_call();
+            writer.write_record(LogLevel::DEBUG, b"last replayed command");
+            set_state(&context, State::Processing, 1);
+        }
+        writer.write_record(LogLevel::DEBUG, b"processing");
+
+        assert_eq!(
+            delivered(&buffer),
+            [
+                (
+                    "last replayed command".to_owned(),
+                    Some("Processing"),
+                    Some(1)
+                ),
+                ("processing".to_owned(), Some("Processing"), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_records_get_the_context_appended() {
+        let (mut writer, context, buffer) = test_writer(false);
+        writer.json = true;
+        set_state(&context, State::Processing, 2);
+
+        writer.write_record(LogLevel::INFO, br#"{"level":"INFO"}"#);
+
+        assert_eq!(
+            delivered(&buffer)[0].0,
+            r#"{"level":"INFO","context":{"invocation_id":"inv","command_index":2,"state":"Processing"}}"#
+        );
+    }
+}
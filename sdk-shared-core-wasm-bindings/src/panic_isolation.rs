@@ -0,0 +1,90 @@
+use crate::WasmResponseHead;
+use restate_sdk_shared_core::Error;
+use std::cell::Cell;
+
+/// Value returned by a [`crate::WasmVM`] call when the VM panicked, or it's poisoned by a previous panic.
+pub(crate) trait FromPanic {
+    // Only used with panic isolation, but the bound is always checked to keep both builds in sync
+    #[cfg_attr(not(feature = "panic-isolation"), allow(dead_code))]
+    fn from_panic(error: Error) -> Self;
+}
+
+impl<T> FromPanic for Result<T, Error> {
+    fn from_panic(error: Error) -> Self {
+        Err(error)
+    }
+}
+
+/// A poisoned VM isn't processing anymore, see [`crate::WasmVM::is_processing`].
+impl FromPanic for bool {
+    fn from_panic(_: Error) -> Self {
+        false
+    }
+}
+
+impl FromPanic for WasmResponseHead {
+    fn from_panic(error: Error) -> Self {
+        WasmResponseHead {
+            status_code: error.code(),
+            headers: vec![],
+        }
+    }
+}
+
+/// Runs `f`, catching its panics and marking the VM as `poisoned`.
+///
+/// Catching panics requires building with `panic = "unwind"`, see the `release-isolated` profile.
+#[cfg(feature = "panic-isolation")]
+pub(crate) fn isolate<R: FromPanic>(poisoned: &Cell<bool>, f: impl FnOnce() -> R) -> R {
+    if poisoned.get() {
+        return R::from_panic(Error::internal(
+            "The VM is poisoned by a previous panic, it cannot be used anymore",
+        ));
+    }
+
+    // The VM is never touched again after a panic, so there's no broken invariant to observe.
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            poisoned.set(true);
+            R::from_panic(Error::internal(format!(
+                "The VM panicked: {}",
+                crate::panic_report::panic_message(&*payload)
+                    .unwrap_or("unknown panic payload".into())
+            )))
+        }
+    }
+}
+
+#[cfg(not(feature = "panic-isolation"))]
+pub(crate) fn isolate<R: FromPanic>(_: &Cell<bool>, f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+#[cfg(all(test, feature = "panic-isolation"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_poisons_the_vm() {
+        let poisoned = Cell::new(false);
+
+        let res: Result<(), Error> = isolate(&poisoned, || panic!("boom"));
+
+        assert_eq!(res.unwrap_err().message(), "The VM panicked: boom");
+        assert!(poisoned.get());
+    }
+
+    #[test]
+    fn poisoned_vm_fails_every_call() {
+        let poisoned = Cell::new(true);
+
+        let res: Result<(), Error> = isolate(&poisoned, || unreachable!());
+
+        assert_eq!(
+            res.unwrap_err().message(),
+            "The VM is poisoned by a previous panic, it cannot be used anymore"
+        );
+        assert!(!isolate(&poisoned, || -> bool { unreachable!() }));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::MakeWebConsoleWriter;
+use serde::Serialize;
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::PanicHookInfo;
+use tsify::Tsify;
+
+/// Report delivered to `fatal` when the shared core panics.
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+pub struct WasmPanicReport {
+    pub message: String,
+    /// Source location of the panic, formatted as `file:line:column`.
+    pub location: Option<String>,
+    /// Logger id of the [`crate::WasmVM`] that was running when the panic happened.
+    pub logger_id: Option<u32>,
+    pub invocation_id: Option<String>,
+    /// Same as [`crate::WasmVM::last_command_index`].
+    pub command_index: Option<i32>,
+    /// True if the panic is caught, poisoning only the VM it originated from.
+    /// This requires the `panic-isolation` feature.
+    pub recoverable: bool,
+}
+
+struct ActiveVM {
+    logger_id: u32,
+    log_writer: MakeWebConsoleWriter,
+}
+
+thread_local! {
+    static ACTIVE_VM: RefCell<Option<ActiveVM>> = const { RefCell::new(None) };
+}
+
+/// Marks a VM as active until dropped, to attribute panics to it.
+pub(crate) struct ActiveVMGuard {
+    previous: Option<ActiveVM>,
+}
+
+impl ActiveVMGuard {
+    pub(crate) fn enter(logger_id: u32, log_writer: MakeWebConsoleWriter) -> Self {
+        let previous = ACTIVE_VM.with(|active_vm| {
+            active_vm.borrow_mut().replace(ActiveVM {
+                logger_id,
+                log_writer,
+            })
+        });
+        Self { previous }
+    }
+}
+
+impl Drop for ActiveVMGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACTIVE_VM.with(|active_vm| *active_vm.borrow_mut() = previous);
+    }
+}
+
+impl WasmPanicReport {
+    /// This runs inside the panic hook, hence it must not panic itself.
+    pub(crate) fn new(info: &PanicHookInfo<'_>) -> Self {
+        let mut report = Self {
+            message: panic_message(info.payload()).unwrap_or_else(|| info.to_string()),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            logger_id: None,
+            invocation_id: None,
+            command_index: None,
+            recoverable: false,
+        };
+
+        let _ = ACTIVE_VM.try_with(|active_vm| {
+            let Ok(active_vm) = active_vm.try_borrow() else {
+                return;
+            };
+            let Some(active_vm) = active_vm.as_ref() else {
+                return;
+            };
+            report.logger_id = Some(active_vm.logger_id);
+            report.recoverable = cfg!(feature = "panic-isolation");
+            // The panic might have happened while holding the lock
+            if let Some(Ok(log_context)) =
+                active_vm.log_writer.context.as_ref().map(|c| c.try_lock())
+            {
+                report.invocation_id = log_context.invocation_id.clone();
+                report.command_index = Some(log_context.command_index);
+            };
+        });
+
+        report
+    }
+}
+
+/// Delivers the log records emitted by the active VM before the panic, see [`MakeWebConsoleWriter::flush_on_panic`].
+/// This runs inside the panic hook, hence it must not panic itself.
+pub(crate) fn flush_active_vm_logs() {
+    let _ = ACTIVE_VM.try_with(|active_vm| {
+        if let Ok(active_vm) = active_vm.try_borrow() {
+            if let Some(active_vm) = active_vm.as_ref() {
+                active_vm.log_writer.flush_on_panic();
+            }
+        }
+    });
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        Some((*s).to_owned())
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
+}
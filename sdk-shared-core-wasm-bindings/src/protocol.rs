@@ -0,0 +1,421 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::convert::{TryFrom, TryInto};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+// See https://github.com/restatedev/service-protocol/blob/main/service-invocation-protocol.md#message-header
+const HEADER_LENGTH: usize = 8;
+
+const COMMAND_MASK: u16 = 0x0400;
+const NOTIFICATION_MASK: u16 = 0x8000;
+const CUSTOM_MASK: u16 = 0xFC00;
+
+/// All the command messages have the `name` field with this tag.
+const COMMAND_NAME_TAG: u64 = 12;
+
+/// Length delimited fields longer than this are truncated in the preview.
+const PREVIEW_BYTES_LIMIT: usize = 256;
+/// Nested messages are decoded in the preview up to this depth.
+const PREVIEW_MAX_DEPTH: usize = 8;
+
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// A service protocol message, see [`decode_protocol_messages`] and [`encode_protocol_messages`].
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)]
+pub struct WasmProtocolMessage {
+    pub message_type: u16,
+    pub flags: u16,
+    #[tsify(type = "Uint8Array")]
+    pub payload: serde_bytes::ByteBuf,
+
+    // The following fields are filled by the decoder, and ignored by the encoder.
+    /// Name of the message type, e.g. `CallCommand`.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub message_type_name: Option<String>,
+    /// Index of the command within the decoded buffer, set only for command messages.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub command_index: Option<u32>,
+    /// Name of the command, set only for command messages with a non-empty name.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Payload decoded as a generic protobuf message, keyed by field number.
+    /// Bytes which aren't valid UTF-8 are shown as hex, and long values are truncated.
+    #[tsify(optional, type = "any")]
+    #[serde(default)]
+    pub payload_preview: Option<JsonValue>,
+}
+
+/// Decodes a buffer of service protocol frames, as passed to `WasmVM.notify_input` or returned by `WasmVM.take_output`.
+///
+/// Commands are numbered starting from `first_command_index`, defaulting to 0.
+#[wasm_bindgen]
+pub fn decode_protocol_messages(
+    buffer: &[u8],
+    first_command_index: Option<u32>,
+) -> Result<Vec<WasmProtocolMessage>, JsError> {
+    decode_messages(buffer, first_command_index).map_err(|e| JsError::new(&e))
+}
+
+/// Encodes the given messages as service protocol frames, the inverse of [`decode_protocol_messages`].
+#[wasm_bindgen]
+pub fn encode_protocol_messages(messages: Vec<WasmProtocolMessage>) -> Result<Vec<u8>, JsError> {
+    encode_messages(messages).map_err(|e| JsError::new(&e))
+}
+
+fn decode_messages(
+    buffer: &[u8],
+    first_command_index: Option<u32>,
+) -> Result<Vec<WasmProtocolMessage>, String> {
+    let mut messages = vec![];
+    let mut next_command_index = first_command_index.unwrap_or_default();
+    let mut remaining = buffer;
+
+    while !remaining.is_empty() {
+        if remaining.len() < HEADER_LENGTH {
+            return Err(format!(
+                "Truncated message header, expected {HEADER_LENGTH} bytes but got {}",
+                remaining.len()
+            ));
+        }
+        let message_type = u16::from_be_bytes([remaining[0], remaining[1]]);
+        let flags = u16::from_be_bytes([remaining[2], remaining[3]]);
+        let length =
+            u32::from_be_bytes([remaining[4], remaining[5], remaining[6], remaining[7]]) as usize;
+        remaining = &remaining[HEADER_LENGTH..];
+        if remaining.len() < length {
+            return Err(format!(
+                "Truncated message payload of type {message_type:#06x}, expected {length} bytes but got {}",
+                remaining.len()
+            ));
+        }
+        let (payload, rest) = remaining.split_at(length);
+        remaining = rest;
+
+        let command_index = is_command(message_type).then(|| {
+            next_command_index += 1;
+            next_command_index - 1
+        });
+        let (name, payload_preview) = inspect_payload(message_type, payload);
+
+        messages.push(WasmProtocolMessage {
+            message_type,
+            flags,
+            payload: payload.to_vec().into(),
+            message_type_name: Some(message_type_name(message_type)),
+            command_index,
+            name,
+            payload_preview: Some(payload_preview),
+        });
+    }
+
+    Ok(messages)
+}
+
+fn encode_messages(messages: Vec<WasmProtocolMessage>) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::with_capacity(
+        messages
+            .iter()
+            .map(|m| HEADER_LENGTH + m.payload.len())
+            .sum(),
+    );
+    for message in messages {
+        let length = u32::try_from(message.payload.len())
+            .map_err(|_| "Message payload too large".to_owned())?;
+        buffer.extend_from_slice(&message.message_type.to_be_bytes());
+        buffer.extend_from_slice(&message.flags.to_be_bytes());
+        buffer.extend_from_slice(&length.to_be_bytes());
+        buffer.extend_from_slice(&message.payload);
+    }
+    Ok(buffer)
+}
+
+pub(crate) fn is_command(message_type: u16) -> bool {
+    (COMMAND_MASK..NOTIFICATION_MASK).contains(&message_type)
+}
+
+/// Returns the command name, if any, and the payload preview.
+pub(crate) fn inspect_payload(message_type: u16, payload: &[u8]) -> (Option<String>, JsonValue) {
+    let Some(fields) = decode_fields(payload) else {
+        return (None, JsonValue::String(preview_bytes(payload)));
+    };
+    let name = if is_command(message_type) {
+        fields.iter().find_map(|(tag, value)| match value {
+            WireValue::LengthDelimited(b) if *tag == COMMAND_NAME_TAG && !b.is_empty() => {
+                std::str::from_utf8(b).ok().map(ToOwned::to_owned)
+            }
+            _ => None,
+        })
+    } else {
+        None
+    };
+    (name, preview_fields(fields, 0))
+}
+
+pub(crate) fn message_type_name(message_type: u16) -> String {
+    match message_type {
+        0x0000 => "Start",
+        0x0001 => "Suspension",
+        0x0002 => "Error",
+        0x0003 => "End",
+        0x0005 => "ProposeRunCompletion",
+        0x0006 => "AwaitingOn",
+        0x0007 => "ProposeRunCompletionAck",
+        0x0400 => "InputCommand",
+        0x0401 => "OutputCommand",
+        0x0402 => "GetLazyStateCommand",
+        0x8002 => "GetLazyStateCompletionNotification",
+        0x0403 => "SetStateCommand",
+        0x0404 => "ClearStateCommand",
+        0x0405 => "ClearAllStateCommand",
+        0x0406 => "GetLazyStateKeysCommand",
+        0x8006 => "GetLazyStateKeysCompletionNotification",
+        0x0407 => "GetEagerStateCommand",
+        0x0408 => "GetEagerStateKeysCommand",
+        0x0409 => "GetPromiseCommand",
+        0x8009 => "GetPromiseCompletionNotification",
+        0x040A => "PeekPromiseCommand",
+        0x800A => "PeekPromiseCompletionNotification",
+        0x040B => "CompletePromiseCommand",
+        0x800B => "CompletePromiseCompletionNotification",
+        0x040C => "SleepCommand",
+        0x800C => "SleepCompletionNotification",
+        0x040D => "CallCommand",
+        0x800E => "CallInvocationIdCompletionNotification",
+        0x800D => "CallCompletionNotification",
+        0x040E => "OneWayCallCommand",
+        0x0410 => "SendSignalCommand",
+        0x0411 => "RunCommand",
+        0x8011 => "RunCompletionNotification",
+        0x0412 => "AttachInvocationCommand",
+        0x8012 => "AttachInvocationCompletionNotification",
+        0x0413 => "GetInvocationOutputCommand",
+        0x8013 => "GetInvocationOutputCompletionNotification",
+        0x0414 => "CompleteAwakeableCommand",
+        0xFBFF => "SignalNotification",
+        ty if ty & CUSTOM_MASK == CUSTOM_MASK => return format!("CustomEntry({ty:#06x})"),
+        ty => return format!("Unknown({ty:#06x})"),
+    }
+    .to_owned()
+}
+
+// --- Generic protobuf decoding, used for the payload preview
+
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    LengthDelimited(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Returns `None` if the buffer is not a valid protobuf message.
+fn decode_fields(mut buf: &[u8]) -> Option<Vec<(u64, WireValue<'_>)>> {
+    let mut fields = vec![];
+    while !buf.is_empty() {
+        let key = decode_varint(&mut buf)?;
+        let tag = key >> 3;
+        if tag == 0 {
+            return None;
+        }
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(decode_varint(&mut buf)?),
+            1 => {
+                let (value, rest) = split_at_checked(buf, 8)?;
+                buf = rest;
+                WireValue::Fixed64(u64::from_le_bytes(value.try_into().ok()?))
+            }
+            2 => {
+                let length = usize::try_from(decode_varint(&mut buf)?).ok()?;
+                let (value, rest) = split_at_checked(buf, length)?;
+                buf = rest;
+                WireValue::LengthDelimited(value)
+            }
+            5 => {
+                let (value, rest) = split_at_checked(buf, 4)?;
+                buf = rest;
+                WireValue::Fixed32(u32::from_le_bytes(value.try_into().ok()?))
+            }
+            // Groups are deprecated and not used by the service protocol
+            _ => return None,
+        };
+        fields.push((tag, value));
+    }
+    Some(fields)
+}
+
+fn decode_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, b) in buf.iter().enumerate().take(10) {
+        value |= u64::from(b & 0x7F) << (7 * i);
+        if b & 0x80 == 0 {
+            *buf = &buf[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn split_at_checked(buf: &[u8], mid: usize) -> Option<(&[u8], &[u8])> {
+    (mid <= buf.len()).then(|| buf.split_at(mid))
+}
+
+/// Repeated fields are collected in arrays.
+fn preview_fields(fields: Vec<(u64, WireValue<'_>)>, depth: usize) -> JsonValue {
+    let mut map = Map::new();
+    for (tag, value) in fields {
+        let value = preview_value(value, depth);
+        match map.get_mut(&tag.to_string()) {
+            Some(JsonValue::Array(values)) => values.push(value),
+            Some(previous) => *previous = JsonValue::Array(vec![previous.take(), value]),
+            None => {
+                map.insert(tag.to_string(), value);
+            }
+        }
+    }
+    JsonValue::Object(map)
+}
+
+fn preview_value(value: WireValue<'_>, depth: usize) -> JsonValue {
+    match value {
+        WireValue::Varint(v) | WireValue::Fixed64(v) if v > MAX_SAFE_INTEGER => {
+            v.to_string().into()
+        }
+        WireValue::Varint(v) | WireValue::Fixed64(v) => v.into(),
+        WireValue::Fixed32(v) => v.into(),
+        WireValue::LengthDelimited(b) => {
+            // Length delimited fields are ambiguous, prefer strings over nested messages
+            if let Ok(s) = std::str::from_utf8(b) {
+                if !s.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+                    return truncate_str(s).into();
+                }
+            }
+            if depth < PREVIEW_MAX_DEPTH && !b.is_empty() {
+                if let Some(fields) = decode_fields(b) {
+                    return preview_fields(fields, depth + 1);
+                }
+            }
+            preview_bytes(b).into()
+        }
+    }
+}
+
+fn truncate_str(s: &str) -> String {
+    if s.len() <= PREVIEW_BYTES_LIMIT {
+        return s.to_owned();
+    }
+    let mut end = PREVIEW_BYTES_LIMIT;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &s[..end], s.len())
+}
+
+fn preview_bytes(b: &[u8]) -> String {
+    let mut hex: String = b
+        .iter()
+        .take(PREVIEW_BYTES_LIMIT)
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if b.len() > PREVIEW_BYTES_LIMIT {
+        hex.push_str(&format!("... ({} bytes)", b.len()));
+    }
+    format!("0x{hex}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(message_type: u16, flags: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend_from_slice(&message_type.to_be_bytes());
+        buf.extend_from_slice(&flags.to_be_bytes());
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    fn sample_buffer() -> Vec<u8> {
+        [
+            // Start, no payload
+            frame(0x0000, 0, &[]),
+            // InputCommand with name = "greet"
+            frame(0x0400, 0, b"\x62\x05greet"),
+            // RunCompletionNotification with completion_id = 3
+            frame(0x8011, 1, &[0x08, 0x03]),
+            // OneWayCallCommand without name
+            frame(0x040E, 0, &[]),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn decode_messages_fills_the_inspection_fields() {
+        let messages = decode_messages(&sample_buffer(), Some(2)).unwrap();
+
+        assert_eq!(messages.len(), 4);
+
+        assert_eq!(messages[0].message_type_name.as_deref(), Some("Start"));
+        assert_eq!(messages[0].command_index, None);
+
+        assert_eq!(
+            messages[1].message_type_name.as_deref(),
+            Some("InputCommand")
+        );
+        assert_eq!(messages[1].command_index, Some(2));
+        assert_eq!(messages[1].name.as_deref(), Some("greet"));
+        assert_eq!(messages[1].payload_preview, Some(json!({"12": "greet"})));
+
+        assert_eq!(
+            messages[2].message_type_name.as_deref(),
+            Some("RunCompletionNotification")
+        );
+        assert_eq!(messages[2].flags, 1);
+        assert_eq!(messages[2].command_index, None);
+        assert_eq!(messages[2].name, None);
+        assert_eq!(messages[2].payload_preview, Some(json!({"1": 3})));
+
+        assert_eq!(messages[3].command_index, Some(3));
+        assert_eq!(messages[3].name, None);
+    }
+
+    #[test]
+    fn encode_is_the_inverse_of_decode() {
+        let buffer = sample_buffer();
+        let messages = decode_messages(&buffer, None).unwrap();
+
+        assert_eq!(encode_messages(messages).unwrap(), buffer);
+    }
+
+    #[test]
+    fn decode_empty_buffer() {
+        assert!(decode_messages(&[], None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decode_truncated_header() {
+        let mut buffer = frame(0x0000, 0, &[]);
+        buffer.extend_from_slice(&[0x04, 0x00, 0x00]);
+
+        assert_eq!(
+            decode_messages(&buffer, None).err().unwrap(),
+            "Truncated message header, expected 8 bytes but got 3"
+        );
+    }
+
+    #[test]
+    fn decode_truncated_payload() {
+        let mut buffer = frame(0x0400, 0, b"\x62\x05greet");
+        buffer.truncate(buffer.len() - 2);
+
+        assert_eq!(
+            decode_messages(&buffer, None).err().unwrap(),
+            "Truncated message payload of type 0x0400, expected 7 bytes but got 5"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::json_format::JsonVisitor;
+use crate::{vm_span_event, LogLevel};
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use tsify::Tsify;
+
+/// Span lifecycle event forwarded to `vm_span_event`.
+///
+/// Span ids are unique within a single [`crate::WasmVM`], and are encoded as strings
+/// because they don't fit the JavaScript number precision.
+/// Timestamps are milliseconds since the unix epoch.
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, hashmap_as_object)]
+pub enum WasmSpanEvent {
+    Open {
+        id: String,
+        parent_id: Option<String>,
+        name: &'static str,
+        target: &'static str,
+        /// One of the [`LogLevel`] values.
+        level: u8,
+        timestamp: f64,
+        #[tsify(type = "Record<string, unknown>")]
+        fields: Map<String, JsonValue>,
+    },
+    Record {
+        id: String,
+        timestamp: f64,
+        #[tsify(type = "Record<string, unknown>")]
+        fields: Map<String, JsonValue>,
+    },
+    Close {
+        id: String,
+        timestamp: f64,
+    },
+}
+
+/// Layer forwarding span open, record and close events to `vm_span_event`.
+pub struct SpanExportLayer {
+    logger_id: Option<u32>,
+    export: fn(WasmSpanEvent, Option<u32>),
+    now: fn() -> f64,
+}
+
+impl SpanExportLayer {
+    pub fn new(logger_id: Option<u32>) -> Self {
+        Self {
+            logger_id,
+            export: vm_span_event,
+            now: js_sys::Date::now,
+        }
+    }
+}
+
+impl<S> Layer<S> for SpanExportLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let meta = attrs.metadata();
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+
+        (self.export)(
+            WasmSpanEvent::Open {
+                id: span_id(id),
+                parent_id: ctx
+                    .span(id)
+                    .and_then(|span| span.parent())
+                    .map(|parent| span_id(&parent.id())),
+                name: meta.name(),
+                target: meta.target(),
+                level: match *meta.level() {
+                    Level::TRACE => LogLevel::TRACE,
+                    Level::DEBUG => LogLevel::DEBUG,
+                    Level::INFO => LogLevel::INFO,
+                    Level::WARN => LogLevel::WARN,
+                    Level::ERROR => LogLevel::ERROR,
+                } as u8,
+                timestamp: (self.now)(),
+                fields,
+            },
+            self.logger_id,
+        );
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Map::new();
+        values.record(&mut JsonVisitor(&mut fields));
+
+        (self.export)(
+            WasmSpanEvent::Record {
+                id: span_id(id),
+                timestamp: (self.now)(),
+                fields,
+            },
+            self.logger_id,
+        );
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        (self.export)(
+            WasmSpanEvent::Close {
+                id: span_id(&id),
+                timestamp: (self.now)(),
+            },
+            self.logger_id,
+        );
+    }
+}
+
+fn span_id(id: &Id) -> String {
+    id.into_u64().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+
+    thread_local! {
+        static EXPORTED: RefCell<Vec<(JsonValue, Option<u32>)>> = const { RefCell::new(vec![]) };
+    }
+
+    fn export(event: WasmSpanEvent, logger_id: Option<u32>) {
+        let event = serde_json::to_value(event).unwrap();
+        EXPORTED.with(|exported| exported.borrow_mut().push((event, logger_id)));
+    }
+
+    #[test]
+    fn span_lifecycle_is_exported() {
+        let layer = SpanExportLayer {
+            logger_id: Some(7),
+            export,
+            now: || 1.5,
+        };
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let outer = tracing::info_span!("invoke", service = "Greeter");
+            let _outer = outer.enter();
+            let inner = tracing::debug_span!("run", name = tracing::field::Empty);
+            inner.record("name", "charge");
+        });
+
+        let exported = EXPORTED.with(|exported| exported.take());
+        assert!(exported.iter().all(|(_, logger_id)| *logger_id == Some(7)));
+        let events: Vec<_> = exported.into_iter().map(|(event, _)| event).collect();
+        // Span ids are assigned by the registry, depending on the thread
+        let outer = &events[0]["Open"]["id"];
+        let inner = &events[1]["Open"]["id"];
+        assert_ne!(outer, inner);
+        assert_eq!(
+            events,
+            [
+                serde_json::json!({ "Open": {
+                    "id": outer,
+                    "parent_id": null,
+                    "name": "invoke",
+                    "target": module_path!(),
+                    "level": LogLevel::INFO as u8,
+                    "timestamp": 1.5,
+                    "fields": { "service": "Greeter" },
+                }}),
+                serde_json::json!({ "Open": {
+                    "id": inner,
+                    "parent_id": outer,
+                    "name": "run",
+                    "target": module_path!(),
+                    "level": LogLevel::DEBUG as u8,
+                    "timestamp": 1.5,
+                    "fields": {},
+                }}),
+                serde_json::json!({ "Record": {
+                    "id": inner,
+                    "timestamp": 1.5,
+                    "fields": { "name": "charge" },
+                }}),
+                serde_json::json!({ "Close": { "id": inner, "timestamp": 1.5 } }),
+                serde_json::json!({ "Close": { "id": outer, "timestamp": 1.5 } }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,701 @@
+use crate::{
+    LogLevel, WasmFailure, WasmHeader, WasmJournalMismatchBehavior, WasmNotificationHandle,
+    WasmNotifyErrorOptions, WasmPayloadOptions, WasmRetryPolicy, WasmRunResult,
+    WasmUnresolvedFuture, WasmVM, WasmVMOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+/// Default of [`WasmVMOptions::record_trace_max_bytes`].
+const DEFAULT_TRACE_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Recording of a [`WasmVM`], see [`WasmVMOptions::record_trace`].
+///
+/// It contains the arguments used to create the VM, followed by every input, syscall and output in the same order they happened.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct InvocationTrace {
+    headers: Vec<TraceHeader>,
+    disable_payload_checks: bool,
+    explicit_cancellation: bool,
+    on_journal_mismatch: WasmJournalMismatchBehavior,
+    options: WasmVMOptions,
+    events: Vec<TraceEvent>,
+    /// If true, the events after the size limit was reached are missing.
+    #[serde(default)]
+    truncated: bool,
+    #[serde(skip)]
+    max_bytes: usize,
+    #[serde(skip)]
+    recorded_bytes: usize,
+}
+
+impl InvocationTrace {
+    pub(crate) fn new(
+        headers: &[WasmHeader],
+        disable_payload_checks: bool,
+        explicit_cancellation: bool,
+        on_journal_mismatch: WasmJournalMismatchBehavior,
+        options: &WasmVMOptions,
+    ) -> Self {
+        Self {
+            headers: redacted_request_headers(headers),
+            disable_payload_checks,
+            explicit_cancellation,
+            on_journal_mismatch,
+            options: WasmVMOptions {
+                record_trace: false,
+                ..options.clone()
+            },
+            events: vec![],
+            truncated: false,
+            max_bytes: options
+                .record_trace_max_bytes
+                .map_or(DEFAULT_TRACE_MAX_BYTES, |max_bytes| max_bytes as usize),
+            recorded_bytes: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, event: TraceEvent) {
+        if self.truncated {
+            return;
+        }
+        let mut counter = ByteCounter(0);
+        if serde_json::to_writer(&mut counter, &event).is_err() {
+            return;
+        }
+        if self.recorded_bytes + counter.0 > self.max_bytes {
+            self.truncated = true;
+            return;
+        }
+        self.recorded_bytes += counter.0;
+        self.events.push(event);
+    }
+}
+
+/// Counts the bytes written, to measure events without allocating their JSON representation.
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encodes byte buffers as base64 strings, instead of arrays of numbers.
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        STANDARD
+            .decode(<&str>::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TraceHeader {
+    key: String,
+    value: String,
+}
+
+impl From<WasmHeader> for TraceHeader {
+    fn from(WasmHeader { key, value }: WasmHeader) -> Self {
+        Self { key, value }
+    }
+}
+
+impl From<TraceHeader> for WasmHeader {
+    fn from(TraceHeader { key, value }: TraceHeader) -> Self {
+        Self { key, value }
+    }
+}
+
+/// Each variant matches the [`WasmVM`] method with the same name.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum TraceEvent {
+    NotifyInput {
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+    },
+    NotifyInputClosed,
+    NotifyError {
+        options: WasmNotifyErrorOptions,
+    },
+    /// The output returned to the SDK, compared against the replayed output.
+    TakeOutput {
+        #[serde(with = "base64_bytes")]
+        output: Vec<u8>,
+    },
+    DoProgress {
+        future: WasmUnresolvedFuture,
+    },
+    TakeNotification {
+        handle: WasmNotificationHandle,
+    },
+    SysInput,
+    SysGetState {
+        key: String,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysGetStateKeys,
+    SysSetState {
+        key: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysClearState {
+        key: String,
+    },
+    SysClearAllState,
+    SysSleep {
+        millis: u64,
+        name: Option<String>,
+        /// Time at which the syscall happened, in millis since unix epoch.
+        now: u64,
+    },
+    SysAttachInvocation {
+        invocation_id: String,
+    },
+    SysGetInvocationOutput {
+        invocation_id: String,
+    },
+    SysCall {
+        service: String,
+        handler: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        key: Option<String>,
+        headers: Vec<TraceHeader>,
+        idempotency_key: Option<String>,
+        scope: Option<String>,
+        limit_key: Option<String>,
+        name: Option<String>,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysSend {
+        service: String,
+        handler: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        key: Option<String>,
+        headers: Vec<TraceHeader>,
+        delay: Option<u64>,
+        idempotency_key: Option<String>,
+        scope: Option<String>,
+        limit_key: Option<String>,
+        name: Option<String>,
+        /// Time at which the syscall happened, in millis since unix epoch.
+        now: u64,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysAwakeable,
+    SysCompleteAwakeableSuccess {
+        id: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysCompleteAwakeableFailure {
+        id: String,
+        value: WasmFailure,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysSignal {
+        signal_name: String,
+    },
+    SysCompleteSignalSuccess {
+        invocation_id: String,
+        signal_name: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+    },
+    SysCompleteSignalFailure {
+        invocation_id: String,
+        signal_name: String,
+        value: WasmFailure,
+    },
+    SysGetPromise {
+        key: String,
+    },
+    SysPeekPromise {
+        key: String,
+    },
+    SysCompletePromiseSuccess {
+        key: String,
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysCompletePromiseFailure {
+        key: String,
+        value: WasmFailure,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysRun {
+        name: String,
+    },
+    ProposeRunCompletion {
+        handle: WasmNotificationHandle,
+        result: WasmRunResult,
+        retry_policy: Option<WasmRetryPolicy>,
+    },
+    SysCancelInvocation {
+        target_invocation_id: String,
+    },
+    SysWriteOutputSuccess {
+        #[serde(with = "base64_bytes")]
+        buffer: Vec<u8>,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysWriteOutputFailure {
+        value: WasmFailure,
+        #[serde(default)]
+        options: Option<WasmPayloadOptions>,
+    },
+    SysEnd,
+}
+
+pub(crate) fn trace_headers(headers: &[WasmHeader]) -> Vec<TraceHeader> {
+    headers.iter().cloned().map(Into::into).collect()
+}
+
+/// Request headers carrying credentials, their value is not recorded.
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-restate-jwt-v1",
+];
+const REDACTED_HEADER_PREFIXES: &[&str] = &["x-restate-signature-"];
+const REDACTED_VALUE: &str = "<redacted>";
+
+/// Only the invocation headers are redacted: the headers of the outgoing calls end up in the recorded outputs anyway,
+/// and the replay compares them.
+fn redacted_request_headers(headers: &[WasmHeader]) -> Vec<TraceHeader> {
+    headers
+        .iter()
+        .map(|WasmHeader { key, value }| {
+            let lowercase_key = key.to_ascii_lowercase();
+            let redact = REDACTED_HEADERS.contains(&lowercase_key.as_str())
+                || REDACTED_HEADER_PREFIXES
+                    .iter()
+                    .any(|prefix| lowercase_key.starts_with(prefix));
+            TraceHeader {
+                key: key.clone(),
+                value: if redact {
+                    REDACTED_VALUE.to_owned()
+                } else {
+                    value.clone()
+                },
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn trace_now(now: Duration) -> u64 {
+    now.as_millis() as u64
+}
+
+/// Result of [`replay_invocation_trace`].
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+pub struct WasmTraceReplayResult {
+    /// Number of trace events replayed, including the mismatching one.
+    pub replayed_events: u32,
+    /// First output which differs from the recorded one, if any.
+    pub mismatch: Option<WasmTraceOutputMismatch>,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi)]
+pub struct WasmTraceOutputMismatch {
+    /// Index of the `TakeOutput` event within the trace.
+    pub event_index: u32,
+    /// Can be inspected with `decode_protocol_messages`.
+    #[tsify(type = "Uint8Array")]
+    pub expected: serde_bytes::ByteBuf,
+    #[tsify(type = "Uint8Array")]
+    pub actual: serde_bytes::ByteBuf,
+}
+
+/// Replays a trace recorded with [`WasmVMOptions::record_trace`] on a fresh VM, checking its outputs match the recorded ones.
+///
+/// Replay stops at the first output mismatch. Truncated traces are replayed up to the last recorded event.
+#[wasm_bindgen]
+pub fn replay_invocation_trace(
+    trace: &str,
+    log_level: LogLevel,
+    logger_id: u32,
+) -> Result<WasmTraceReplayResult, JsError> {
+    let trace: InvocationTrace = serde_json::from_str(trace)?;
+
+    let mut vm = WasmVM::new(
+        trace.headers.into_iter().map(Into::into).collect(),
+        log_level,
+        logger_id,
+        trace.disable_payload_checks,
+        trace.explicit_cancellation,
+        trace.on_journal_mismatch,
+        Some(trace.options),
+    )
+    .map_err(|f| JsError::new(&f.message))?;
+
+    let replayed_events = trace.events.len() as u32;
+    for (event_index, event) in trace.events.into_iter().enumerate() {
+        // The results of the syscalls are not checked, as they're reflected in the outputs anyway
+        match event {
+            TraceEvent::NotifyInput { buffer } => {
+                let _ = vm.notify_input(buffer);
+            }
+            TraceEvent::NotifyInputClosed => {
+                let _ = vm.notify_input_closed();
+            }
+            TraceEvent::NotifyError { options } => {
+                let _ = vm.notify_error(options);
+            }
+            TraceEvent::TakeOutput { output } => {
+                let actual = vm
+                    .take_output_bytes()
+                    .map_err(|f| JsError::new(&f.message))?;
+                if *actual != *output {
+                    return Ok(WasmTraceReplayResult {
+                        replayed_events: event_index as u32 + 1,
+                        mismatch: Some(WasmTraceOutputMismatch {
+                            event_index: event_index as u32,
+                            expected: output.into(),
+                            actual: actual.to_vec().into(),
+                        }),
+                    });
+                }
+            }
+            TraceEvent::DoProgress { future } => {
+                let _ = vm.do_progress(future);
+            }
+            TraceEvent::TakeNotification { handle } => {
+                let _ = vm.take_notification(handle);
+            }
+            TraceEvent::SysInput => {
+                let _ = vm.sys_input_raw();
+            }
+            TraceEvent::SysGetState { key, options } => {
+                let _ = vm.sys_get_state(key, options);
+            }
+            TraceEvent::SysGetStateKeys => {
+                let _ = vm.sys_get_state_keys();
+            }
+            TraceEvent::SysSetState {
+                key,
+                buffer,
+                options,
+            } => {
+                let _ = vm.sys_set_state(key, buffer, options);
+            }
+            TraceEvent::SysClearState { key } => {
+                let _ = vm.sys_clear_state(key);
+            }
+            TraceEvent::SysClearAllState => {
+                let _ = vm.sys_clear_all_state();
+            }
+            TraceEvent::SysSleep { millis, name, now } => {
+                vm.set_clock_override(Duration::from_millis(now));
+                let _ = vm.sys_sleep(millis, name);
+            }
+            TraceEvent::SysAttachInvocation { invocation_id } => {
+                let _ = vm.sys_attach_invocation(invocation_id);
+            }
+            TraceEvent::SysGetInvocationOutput { invocation_id } => {
+                let _ = vm.sys_get_invocation_output(invocation_id);
+            }
+            TraceEvent::SysCall {
+                service,
+                handler,
+                buffer,
+                key,
+                headers,
+                idempotency_key,
+                scope,
+                limit_key,
+                name,
+                options,
+            } => {
+                let _ = vm.sys_call(
+                    service,
+                    handler,
+                    buffer,
+                    key,
+                    headers.into_iter().map(Into::into).collect(),
+                    idempotency_key,
+                    scope,
+                    limit_key,
+                    name,
+                    options,
+                );
+            }
+            TraceEvent::SysSend {
+                service,
+                handler,
+                buffer,
+                key,
+                headers,
+                delay,
+                idempotency_key,
+                scope,
+                limit_key,
+                name,
+                now,
+                options,
+            } => {
+                vm.set_clock_override(Duration::from_millis(now));
+                let _ = vm.sys_send(
+                    service,
+                    handler,
+                    buffer,
+                    key,
+                    headers.into_iter().map(Into::into).collect(),
+                    delay,
+                    idempotency_key,
+                    scope,
+                    limit_key,
+                    name,
+                    options,
+                );
+            }
+            TraceEvent::SysAwakeable => {
+                let _ = vm.sys_awakeable();
+            }
+            TraceEvent::SysCompleteAwakeableSuccess {
+                id,
+                buffer,
+                options,
+            } => {
+                let _ = vm.sys_complete_awakeable_success(id, buffer, options);
+            }
+            TraceEvent::SysCompleteAwakeableFailure { id, value, options } => {
+                let _ = vm.sys_complete_awakeable_failure(id, value, options);
+            }
+            TraceEvent::SysSignal { signal_name } => {
+                let _ = vm.sys_signal(signal_name);
+            }
+            TraceEvent::SysCompleteSignalSuccess {
+                invocation_id,
+                signal_name,
+                buffer,
+            } => {
+                let _ = vm.sys_complete_signal_success(invocation_id, signal_name, buffer);
+            }
+            TraceEvent::SysCompleteSignalFailure {
+                invocation_id,
+                signal_name,
+                value,
+            } => {
+                let _ = vm.sys_complete_signal_failure(invocation_id, signal_name, value);
+            }
+            TraceEvent::SysGetPromise { key } => {
+                let _ = vm.sys_get_promise(key);
+            }
+            TraceEvent::SysPeekPromise { key } => {
+                let _ = vm.sys_peek_promise(key);
+            }
+            TraceEvent::SysCompletePromiseSuccess {
+                key,
+                buffer,
+                options,
+            } => {
+                let _ = vm.sys_complete_promise_success(key, buffer, options);
+            }
+            TraceEvent::SysCompletePromiseFailure {
+                key,
+                value,
+                options,
+            } => {
+                let _ = vm.sys_complete_promise_failure(key, value, options);
+            }
+            TraceEvent::SysRun { name } => {
+                let _ = vm.sys_run(name);
+            }
+            TraceEvent::ProposeRunCompletion {
+                handle,
+                result,
+                retry_policy,
+            } => {
+                let _ = vm.propose_run_completion(handle, result, retry_policy);
+            }
+            TraceEvent::SysCancelInvocation {
+                target_invocation_id,
+            } => {
+                let _ = vm.sys_cancel_invocation(target_invocation_id);
+            }
+            TraceEvent::SysWriteOutputSuccess { buffer, options } => {
+                let _ = vm.sys_write_output_success(buffer, options);
+            }
+            TraceEvent::SysWriteOutputFailure { value, options } => {
+                let _ = vm.sys_write_output_failure(value, options);
+            }
+            TraceEvent::SysEnd => {
+                let _ = vm.sys_end();
+            }
+        }
+    }
+
+    Ok(WasmTraceReplayResult {
+        replayed_events,
+        mismatch: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend_from_slice(&message_type.to_be_bytes());
+        buf.extend_from_slice(&0u16.to_be_bytes());
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    fn header(key: &str, value: &str) -> WasmHeader {
+        WasmHeader {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    fn record_invocation(record_trace_max_bytes: Option<u32>) -> String {
+        let mut vm = WasmVM::new(
+            vec![
+                header("content-type", "application/vnd.restate.invocation.v5"),
+                header("Authorization", "Bearer secret-token"),
+                header("x-restate-signature-scheme", "v1"),
+                header("x-restate-jwt-v1", "secret-jwt"),
+                header("x-custom", "visible"),
+            ],
+            LogLevel::ERROR,
+            0,
+            false,
+            false,
+            WasmJournalMismatchBehavior::Retry,
+            Some(WasmVMOptions {
+                record_trace: true,
+                record_trace_max_bytes,
+                ..Default::default()
+            }),
+        )
+        .ok()
+        .unwrap();
+
+        let _ = vm.notify_input(
+            [
+                // Start with id, debug_id = "inv" and known_entries = 1
+                frame(0x0000, b"\x0a\x01\x01\x12\x03inv\x18\x01"),
+                // InputCommand with value = "hi"
+                frame(0x0400, b"\x72\x04\x0a\x02hi"),
+            ]
+            .concat(),
+        );
+        let _ = vm.notify_input_closed();
+        assert!(vm.sys_input_raw().is_ok());
+        assert!(vm.sys_write_output_success(b"hello".to_vec(), None).is_ok());
+        assert!(vm.sys_end().is_ok());
+        assert!(!vm.take_output_bytes().ok().unwrap().is_empty());
+
+        vm.invocation_trace().ok().unwrap().unwrap()
+    }
+
+    #[test]
+    fn request_credentials_are_redacted() {
+        let trace: InvocationTrace = serde_json::from_str(&record_invocation(None)).unwrap();
+
+        let headers: Vec<(&str, &str)> = trace
+            .headers
+            .iter()
+            .map(|h| (h.key.as_str(), h.value.as_str()))
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                ("content-type", "application/vnd.restate.invocation.v5"),
+                ("Authorization", REDACTED_VALUE),
+                ("x-restate-signature-scheme", REDACTED_VALUE),
+                ("x-restate-jwt-v1", REDACTED_VALUE),
+                ("x-custom", "visible"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recorded_trace_replays_without_mismatch() {
+        let trace = record_invocation(None);
+
+        let result = replay_invocation_trace(&trace, LogLevel::ERROR, 0)
+            .ok()
+            .unwrap();
+
+        assert_eq!(result.replayed_events, 6);
+        assert!(result.mismatch.is_none());
+    }
+
+    #[test]
+    fn bytes_are_encoded_as_base64() {
+        let trace: serde_json::Value = serde_json::from_str(&record_invocation(None)).unwrap();
+
+        let write_output = trace["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["type"] == "SysWriteOutputSuccess")
+            .unwrap();
+        assert_eq!(write_output["buffer"], "aGVsbG8=");
+        assert_eq!(trace["truncated"], false);
+    }
+
+    #[test]
+    fn recording_stops_at_the_size_limit() {
+        let full: InvocationTrace = serde_json::from_str(&record_invocation(None)).unwrap();
+        // Room for the first two events only
+        let max_bytes = full.events[..2]
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap().len())
+            .sum::<usize>() as u32;
+
+        let trace = record_invocation(Some(max_bytes));
+
+        let truncated: InvocationTrace = serde_json::from_str(&trace).unwrap();
+        assert!(truncated.truncated);
+        assert_eq!(truncated.events.len(), 2);
+        let result = replay_invocation_trace(&trace, LogLevel::ERROR, 0)
+            .ok()
+            .unwrap();
+        assert_eq!(result.replayed_events, 2);
+        assert!(result.mismatch.is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use restate_sdk_shared_core::Header;
+
+// See https://www.w3.org/TR/trace-context/
+const TRACEPARENT_HEADER: &str = "traceparent";
+const TRACESTATE_HEADER: &str = "tracestate";
+
+/// W3C trace context of the current invocation, parsed from the input headers.
+pub(crate) struct TraceContext {
+    traceparent: String,
+    trace_state: Option<String>,
+}
+
+impl TraceContext {
+    /// Returns `None` if the headers don't contain a valid `traceparent`.
+    pub(crate) fn from_headers(headers: &[Header]) -> Option<Self> {
+        let traceparent = find_header(headers, TRACEPARENT_HEADER)?.trim();
+        parse_traceparent(traceparent)?;
+
+        Some(Self {
+            traceparent: traceparent.to_owned(),
+            trace_state: find_header(headers, TRACESTATE_HEADER).map(ToOwned::to_owned),
+        })
+    }
+
+    /// Injects `traceparent` and `tracestate` in the outgoing request headers.
+    ///
+    /// The parent id is the one of the invocation, that is the span exported by the runtime for it,
+    /// so the outgoing requests show up as its children.
+    /// The input headers are journaled, hence the injected headers are the same when the command is replayed.
+    ///
+    /// Headers explicitly set by the caller take precedence.
+    pub(crate) fn inject(&self, headers: &mut Vec<Header>) {
+        if find_header(headers, TRACEPARENT_HEADER).is_some() {
+            return;
+        }
+
+        headers.push(Header {
+            key: TRACEPARENT_HEADER.into(),
+            value: self.traceparent.clone().into(),
+        });
+        if let Some(trace_state) = &self.trace_state {
+            if find_header(headers, TRACESTATE_HEADER).is_none() {
+                headers.push(Header {
+                    key: TRACESTATE_HEADER.into(),
+                    value: trace_state.clone().into(),
+                });
+            }
+        }
+    }
+}
+
+fn find_header<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_ref())
+}
+
+/// Returns trace id and trace flags.
+fn parse_traceparent(value: &str) -> Option<(&str, &str)> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let trace_flags = parts.next()?;
+
+    // Future versions can append fields, but version 00 has exactly 4 fields
+    if (version == "00" && parts.next().is_some())
+        || !is_lower_hex(version, 2)
+        || version == "ff"
+        || !is_lower_hex(trace_id, 32)
+        || trace_id.bytes().all(|b| b == b'0')
+        || !is_lower_hex(parent_id, 16)
+        || parent_id.bytes().all(|b| b == b'0')
+        || !is_lower_hex(trace_flags, 2)
+    {
+        return None;
+    }
+
+    Some((trace_id, trace_flags))
+}
+
+fn is_lower_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn header(key: &str, value: &str) -> Header {
+        Header {
+            key: key.to_owned().into(),
+            value: value.to_owned().into(),
+        }
+    }
+
+    fn pairs(headers: &[Header]) -> Vec<(&str, &str)> {
+        headers
+            .iter()
+            .map(|h| (h.key.as_ref(), h.value.as_ref()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_traceparent() {
+        assert_eq!(
+            parse_traceparent(TRACEPARENT),
+            Some(("4bf92f3577b34da6a3ce929d0e0e4736", "01"))
+        );
+        // Future versions can have more fields
+        assert_eq!(
+            parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra"),
+            Some(("4bf92f3577b34da6a3ce929d0e0e4736", "00"))
+        );
+    }
+
+    #[test]
+    fn malformed_traceparent() {
+        for value in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-1",
+        ] {
+            assert_eq!(parse_traceparent(value), None, "{value}");
+        }
+        assert!(TraceContext::from_headers(&[header("traceparent", "00-invalid-01")]).is_none());
+        assert!(TraceContext::from_headers(&[header("x-custom", "value")]).is_none());
+    }
+
+    #[test]
+    fn inject_is_deterministic_on_replay() {
+        let input_headers = [
+            header("Traceparent", TRACEPARENT),
+            header("tracestate", "vendor=value"),
+        ];
+
+        let inject = || {
+            let trace_context = TraceContext::from_headers(&input_headers).unwrap();
+            let mut headers = vec![header("x-custom", "value")];
+            trace_context.inject(&mut headers);
+            headers
+        };
+        let first_attempt = inject();
+        let replay = inject();
+
+        assert_eq!(
+            pairs(&first_attempt),
+            [
+                ("x-custom", "value"),
+                ("traceparent", TRACEPARENT),
+                ("tracestate", "vendor=value"),
+            ]
+        );
+        assert_eq!(pairs(&first_attempt), pairs(&replay));
+    }
+
+    #[test]
+    fn inject_keeps_explicit_headers() {
+        let trace_context = TraceContext::from_headers(&[
+            header("traceparent", TRACEPARENT),
+            header("tracestate", "vendor=value"),
+        ])
+        .unwrap();
+
+        let explicit = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let mut headers = vec![header("TRACEPARENT", explicit)];
+        trace_context.inject(&mut headers);
+        assert_eq!(pairs(&headers), [("TRACEPARENT", explicit)]);
+
+        let mut headers = vec![header("tracestate", "other=value")];
+        trace_context.inject(&mut headers);
+        assert_eq!(
+            pairs(&headers),
+            [("tracestate", "other=value"), ("traceparent", TRACEPARENT)]
+        );
+    }
+}